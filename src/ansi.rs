@@ -0,0 +1,599 @@
+//! Width and truncation for strings containing ANSI escape sequences, such as colours.
+
+use std::borrow::Cow;
+
+use crate::{str_width_at_with, take, take_at, Ellipsis, Measure, WidthOptions};
+
+const ESC: u8 = 0x1b;
+const BEL: u8 = 0x07;
+
+/// Resets all graphic rendition (SGR) state.
+const RESET: &str = "\x1b[0m";
+
+/// A piece of a string, which is either text or a single escape sequence.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Token<'a> {
+    Text(&'a str),
+    Escape(&'a str),
+}
+
+/// Splits a string into [`Token`]s, alongside their starting byte index.
+struct Tokens<'a> {
+    content: &'a str,
+    index: usize,
+}
+
+impl<'a> Tokens<'a> {
+    fn new(content: &'a str) -> Self {
+        Self { content, index: 0 }
+    }
+}
+
+impl<'a> Iterator for Tokens<'a> {
+    type Item = (usize, Token<'a>);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let start = self.index;
+        let rest = &self.content[start..];
+
+        if rest.is_empty() {
+            None
+        } else if rest.as_bytes()[0] == ESC {
+            let len = escape_len(rest);
+            self.index += len;
+
+            Some((start, Token::Escape(&rest[..len])))
+        } else {
+            let len = rest.bytes().position(|b| b == ESC).unwrap_or(rest.len());
+            self.index += len;
+
+            Some((start, Token::Text(&rest[..len])))
+        }
+    }
+}
+
+/// Returns the length in bytes of the escape sequence at the start of `s`, which must start with
+/// an `ESC`. Unterminated sequences run to the end of `s`.
+///
+/// This handles CSI sequences (like SGR colours), string sequences (like OSC titles and hyperlinks),
+/// and other short `ESC`-prefixed sequences. Sequences always end on an ASCII byte, so they never
+/// split a character.
+fn escape_len(s: &str) -> usize {
+    let bytes = s.as_bytes();
+
+    match bytes.get(1) {
+        None => 1,
+        // CSI: parameters and intermediates, then a final byte in `0x40..=0x7E`.
+        Some(b'[') => bytes[2..]
+            .iter()
+            .position(|b| (0x40..=0x7e).contains(b))
+            .map_or(bytes.len(), |index| index + 3),
+        // OSC, DCS, SOS, PM and APC: a string terminated by either BEL or ST (`ESC \`).
+        Some(b']' | b'P' | b'X' | b'^' | b'_') => {
+            let mut index = 2;
+            while index < bytes.len() {
+                if bytes[index] == BEL {
+                    return index + 1;
+                } else if bytes[index] == ESC && bytes.get(index + 1) == Some(&b'\\') {
+                    return index + 2;
+                }
+                index += 1;
+            }
+
+            bytes.len()
+        }
+        // Anything else: any intermediate bytes, then a single final byte.
+        Some(_) => {
+            let mut index = 1;
+            while index < bytes.len() && (0x20..=0x2f).contains(&bytes[index]) {
+                index += 1;
+            }
+
+            if bytes.get(index).is_some_and(u8::is_ascii) {
+                index + 1
+            } else {
+                index
+            }
+        }
+    }
+}
+
+/// Returns the parameters of `escape` if it is an SGR ("select graphic rendition") sequence.
+#[inline]
+fn sgr_params(escape: &str) -> Option<&str> {
+    escape.strip_prefix("\x1b[")?.strip_suffix('m')
+}
+
+/// Returns the URI of `escape` if it is an OSC 8 hyperlink sequence. An empty URI closes the
+/// current hyperlink.
+#[inline]
+fn hyperlink_uri(escape: &str) -> Option<&str> {
+    let rest = escape.strip_prefix("\x1b]8;")?;
+    let rest = rest
+        .strip_suffix('\x07')
+        .or_else(|| rest.strip_suffix("\x1b\\"))
+        .unwrap_or(rest);
+
+    rest.split_once(';').map(|(_, uri)| uri)
+}
+
+/// Returns the sequence that closes the hyperlink opened by `open`, using the same terminator.
+#[inline]
+fn hyperlink_close(open: &str) -> &'static str {
+    if open.ends_with('\x07') {
+        "\x1b]8;;\x07"
+    } else {
+        "\x1b]8;;\x1b\\"
+    }
+}
+
+/// Tracks the SGR sequences and hyperlink that are currently in effect.
+#[derive(Debug, Default)]
+struct Style<'a> {
+    /// The SGR sequences seen since the last full reset.
+    sequences: Vec<&'a str>,
+    /// The sequence that opened the current hyperlink, if any.
+    hyperlink: Option<&'a str>,
+}
+
+impl<'a> Style<'a> {
+    /// Updates the style with `escape`, if it is an SGR or hyperlink sequence.
+    fn apply(&mut self, escape: &'a str) {
+        fn is_reset(param: &str) -> bool {
+            param.bytes().all(|b| b == b'0')
+        }
+
+        if let Some(uri) = hyperlink_uri(escape) {
+            self.hyperlink = (!uri.is_empty()).then_some(escape);
+            return;
+        }
+
+        let Some(params) = sgr_params(escape) else {
+            return;
+        };
+
+        // Walk the params in order, skipping the arguments of extended colours so that something
+        // like the `0` in `38;5;0` isn't mistaken for a reset.
+        let mut has_reset = false;
+        let mut ends_with_reset = false;
+        let mut params = params.split(';');
+        while let Some(param) = params.next() {
+            ends_with_reset = is_reset(param);
+            has_reset |= ends_with_reset;
+
+            if matches!(param, "38" | "48" | "58") {
+                match params.next() {
+                    Some("5") => {
+                        params.next();
+                    }
+                    Some("2") => {
+                        params.nth(2);
+                    }
+                    _ => {}
+                }
+            }
+        }
+
+        if has_reset {
+            self.sequences.clear();
+        }
+        if !ends_with_reset {
+            self.sequences.push(escape);
+        }
+    }
+
+    #[inline]
+    fn is_active(&self) -> bool {
+        !self.sequences.is_empty()
+    }
+}
+
+/// Returns whether all of `content` fits within `width`, ignoring escape sequences.
+#[inline]
+fn fits_ansi(content: &str, width: usize, options: &WidthOptions) -> bool {
+    (options.is_byte_bounded() && content.len() <= width && !content.contains('\t'))
+        || str_width_ansi_with(content, options) <= width
+}
+
+/// Returns the width of `s` like [`str_width`](crate::str_width), but treating ANSI escape
+/// sequences (such as `"\x1b[31m"`) as zero-width.
+#[inline]
+pub fn str_width_ansi(s: &str) -> usize {
+    str_width_ansi_with(s, &WidthOptions::DEFAULT)
+}
+
+/// Returns the width of `s` like [`str_width_ansi`], measured with the given [`WidthOptions`].
+pub fn str_width_ansi_with(s: &str, options: &WidthOptions) -> usize {
+    Tokens::new(s).fold(0, |width, (_, token)| match token {
+        Token::Text(text) => width + str_width_at_with(text, width, options),
+        Token::Escape(_) => width,
+    })
+}
+
+/// Truncates a string containing ANSI escape sequences to the specified width with a trailing
+/// ellipsis character.
+///
+/// Escape sequences are treated as zero-width and are never split. The ellipsis keeps the style
+/// of the text it replaces, and a reset is added after it if any style is still active, so colours
+/// don't bleed into whatever comes next.
+///
+/// [OSC 8](https://gist.github.com/egmontkob/eb114294efbcd5adb1944c9f3cb5feda) hyperlinks are also
+/// understood; only the link text counts towards the width, and a hyperlink that is cut off is
+/// closed before the ellipsis.
+///
+/// ```
+/// use unicode_ellipsis::truncate_str_ansi;
+///
+/// assert_eq!(
+///     truncate_str_ansi("\x1b[31mhello world\x1b[0m", 8),
+///     "\x1b[31mhello w…\x1b[0m"
+/// );
+/// ```
+#[inline]
+pub fn truncate_str_ansi(content: &str, width: usize) -> Cow<'_, str> {
+    truncate_str_ansi_with(content, width, &WidthOptions::DEFAULT)
+}
+
+/// Truncates a string containing ANSI escape sequences to the specified width like
+/// [`truncate_str_ansi`], measured with the given [`WidthOptions`].
+pub fn truncate_str_ansi_with<'a>(
+    content: &'a str,
+    width: usize,
+    options: &WidthOptions,
+) -> Cow<'a, str> {
+    if fits_ansi(content, width, options) {
+        return content.into();
+    }
+
+    let ellipsis = Ellipsis::new(Ellipsis::DEFAULT.text, options);
+    if ellipsis.width > width {
+        return ellipsis.fit(width, options).into();
+    }
+
+    let mut remaining = width - ellipsis.width;
+    let mut style = Style::default();
+    let mut ret = String::with_capacity(content.len());
+
+    for (_, token) in Tokens::new(content) {
+        match token {
+            Token::Escape(escape) => {
+                ret.push_str(escape);
+                style.apply(escape);
+            }
+            Token::Text(text) => {
+                let column = width - ellipsis.width - remaining;
+                let kept = take_at::<false, _>(text, column, remaining, options);
+                ret.push_str(&text[..kept.bytes]);
+                remaining -= kept.width;
+
+                if kept.bytes < text.len() {
+                    break;
+                }
+            }
+        }
+    }
+
+    if let Some(open) = style.hyperlink {
+        ret.push_str(hyperlink_close(open));
+    }
+    ret.push_str(ellipsis.text);
+    if style.is_active() {
+        ret.push_str(RESET);
+    }
+
+    ret.into()
+}
+
+/// Truncates a string containing ANSI escape sequences to the specified width with a leading
+/// ellipsis character.
+///
+/// Escape sequences are treated as zero-width and are never split. Any styles set in the part
+/// that is cut off are re-emitted at the start, so the rest of the string keeps its style.
+///
+/// [OSC 8](https://gist.github.com/egmontkob/eb114294efbcd5adb1944c9f3cb5feda) hyperlinks are also
+/// understood; only the link text counts towards the width, and a hyperlink that is cut off is
+/// reopened after the ellipsis.
+///
+/// ```
+/// use unicode_ellipsis::truncate_str_leading_ansi;
+///
+/// assert_eq!(
+///     truncate_str_leading_ansi("\x1b[31mhello world\x1b[0m", 6),
+///     "\x1b[31m…world\x1b[0m"
+/// );
+/// ```
+#[inline]
+pub fn truncate_str_leading_ansi(content: &str, width: usize) -> Cow<'_, str> {
+    truncate_str_leading_ansi_with(content, width, &WidthOptions::DEFAULT)
+}
+
+/// Truncates a string containing ANSI escape sequences to the specified width like
+/// [`truncate_str_leading_ansi`], measured with the given [`WidthOptions`].
+pub fn truncate_str_leading_ansi_with<'a>(
+    content: &'a str,
+    width: usize,
+    options: &WidthOptions,
+) -> Cow<'a, str> {
+    if fits_ansi(content, width, options) {
+        return content.into();
+    }
+
+    let ellipsis = Ellipsis::new(Ellipsis::DEFAULT.text, options);
+    if ellipsis.width > width {
+        return ellipsis.fit(width, options).into();
+    }
+
+    let tokens: Vec<_> = Tokens::new(content).collect();
+    let mut remaining = width - ellipsis.width;
+
+    // Find where to cut, by working backwards.
+    let mut cut = (0, 0);
+    for (index, (start, token)) in tokens.iter().enumerate().rev() {
+        if let Token::Text(text) = token {
+            let kept = take::<true, _>(text, remaining, options);
+            remaining -= kept.width;
+
+            if kept.bytes < text.len() {
+                cut = (index, start + text.len() - kept.bytes);
+                break;
+            }
+        }
+    }
+
+    let (cut_index, cut_byte) = cut;
+    let mut style = Style::default();
+    for (_, token) in &tokens[..cut_index] {
+        if let Token::Escape(escape) = token {
+            style.apply(escape);
+        }
+    }
+
+    let kept = &content[cut_byte..];
+
+    // Only reopen the hyperlink if the kept part doesn't immediately open or close one itself.
+    let hyperlink = style.hyperlink.filter(|_| {
+        !Tokens::new(kept)
+            .map_while(|(_, token)| match token {
+                Token::Escape(escape) => Some(escape),
+                Token::Text(_) => None,
+            })
+            .any(|escape| hyperlink_uri(escape).is_some())
+    });
+
+    let mut ret = String::with_capacity(kept.len() + ellipsis.text.len());
+    for sequence in style.sequences {
+        ret.push_str(sequence);
+    }
+    ret.push_str(ellipsis.text);
+    if let Some(open) = hyperlink {
+        ret.push_str(open);
+    }
+    ret.push_str(kept);
+
+    ret.into()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::WidthOverrides;
+
+    #[test]
+    fn test_tokens() {
+        let tokens: Vec<_> = Tokens::new("a\x1b[31mb\x1b]0;title\x07c\x1b(Bd\x1b")
+            .map(|(_, token)| token)
+            .collect();
+
+        assert_eq!(
+            tokens,
+            vec![
+                Token::Text("a"),
+                Token::Escape("\x1b[31m"),
+                Token::Text("b"),
+                Token::Escape("\x1b]0;title\x07"),
+                Token::Text("c"),
+                Token::Escape("\x1b(B"),
+                Token::Text("d"),
+                Token::Escape("\x1b"),
+            ]
+        );
+
+        let tokens: Vec<_> = Tokens::new("\x1b]8;;https://example.com\x1b\\link\x1b[38;5;1")
+            .map(|(_, token)| token)
+            .collect();
+
+        assert_eq!(
+            tokens,
+            vec![
+                Token::Escape("\x1b]8;;https://example.com\x1b\\"),
+                Token::Text("link"),
+                Token::Escape("\x1b[38;5;1"),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_str_width_ansi() {
+        assert_eq!(str_width_ansi(""), 0);
+        assert_eq!(str_width_ansi("plain"), 5);
+        assert_eq!(str_width_ansi("\x1b[31mred\x1b[0m"), 3);
+        assert_eq!(str_width_ansi("\x1b[1;38;5;196m施氏\x1b[m"), 4);
+        assert_eq!(str_width_ansi("\x1b]0;a window title\x07text"), 4);
+        assert_eq!(str_width_ansi("\x1b[31mab\x1b[0m\tc"), 9);
+    }
+
+    #[test]
+    fn test_truncate_str_ansi() {
+        let red = "\x1b[31mhello world\x1b[0m";
+
+        assert_eq!(truncate_str_ansi(red, 20), red);
+        assert_eq!(truncate_str_ansi(red, 11), red);
+        assert_eq!(truncate_str_ansi(red, 10), "\x1b[31mhello wor…\x1b[0m");
+        assert_eq!(truncate_str_ansi(red, 8), "\x1b[31mhello w…\x1b[0m");
+        assert_eq!(truncate_str_ansi(red, 1), "\x1b[31m…\x1b[0m");
+        assert_eq!(truncate_str_ansi(red, 0), "");
+
+        assert_eq!(
+            truncate_str_ansi("plain \x1b[1mbold\x1b[0m", 8),
+            "plain \x1b[1mb…\x1b[0m"
+        );
+        assert_eq!(
+            truncate_str_ansi("abc\x1b[31mdef\x1b[0m", 4),
+            "abc\x1b[31m…\x1b[0m",
+            "the ellipsis should take the style of the text it replaces"
+        );
+        assert_eq!(
+            truncate_str_ansi("\x1b[31mred\x1b[0m plain text", 8),
+            "\x1b[31mred\x1b[0m pla…",
+            "no reset is needed if the style was already reset"
+        );
+        assert_eq!(
+            truncate_str_ansi("\x1b]0;title\x07hello world", 5),
+            "\x1b]0;title\x07hell…"
+        );
+        assert_eq!(
+            truncate_str_ansi("\x1b[32m施氏食獅史\x1b[0m", 6),
+            "\x1b[32m施氏…\x1b[0m"
+        );
+        assert_eq!(
+            truncate_str_ansi("\x1b[31mab\x1b[0m\tcdef", 10),
+            "\x1b[31mab\x1b[0m\tc…"
+        );
+        assert_eq!(truncate_str_ansi("a\tb", 9), "a\tb");
+    }
+
+    #[test]
+    fn test_truncate_str_leading_ansi() {
+        let red = "\x1b[31mhello world\x1b[0m";
+
+        assert_eq!(truncate_str_leading_ansi(red, 11), red);
+        assert_eq!(truncate_str_leading_ansi(red, 6), "\x1b[31m…world\x1b[0m");
+        assert_eq!(truncate_str_leading_ansi(red, 1), "\x1b[31m…\x1b[0m");
+        assert_eq!(truncate_str_leading_ansi(red, 0), "");
+
+        assert_eq!(
+            truncate_str_leading_ansi("\x1b[31mred\x1b[0m plain text", 5),
+            "…text",
+            "styles that were reset should not be re-emitted"
+        );
+        assert_eq!(
+            truncate_str_leading_ansi("abc\x1b[31mdef\x1b[0m", 4),
+            "…\x1b[31mdef\x1b[0m"
+        );
+        assert_eq!(
+            truncate_str_leading_ansi("\x1b[1m\x1b[34mbold blue\x1b[0m", 5),
+            "\x1b[1m\x1b[34m…blue\x1b[0m"
+        );
+        assert_eq!(
+            truncate_str_leading_ansi("\x1b[32m施氏食獅史\x1b[0m", 6),
+            "\x1b[32m…獅史\x1b[0m"
+        );
+    }
+
+    #[test]
+    fn test_ansi_extended_colours() {
+        // A `0` argument to an extended colour is a colour, not a reset.
+        assert_eq!(
+            truncate_str_ansi("\x1b[38;2;255;0;0mhello world", 5),
+            "\x1b[38;2;255;0;0mhell…\x1b[0m"
+        );
+        assert_eq!(
+            truncate_str_ansi("\x1b[38;5;0mhello world", 5),
+            "\x1b[38;5;0mhell…\x1b[0m"
+        );
+        assert_eq!(
+            truncate_str_leading_ansi("\x1b[1m\x1b[38;5;0mhello world\x1b[0m", 5),
+            "\x1b[1m\x1b[38;5;0m…orld\x1b[0m"
+        );
+
+        // Resets are still found around them.
+        assert_eq!(
+            truncate_str_ansi("\x1b[48;5;1;0mhello world", 5),
+            "\x1b[48;5;1;0mhell…"
+        );
+        assert_eq!(
+            truncate_str_leading_ansi("\x1b[1m\x1b[0;38;5;0mhello world\x1b[0m", 5),
+            "\x1b[0;38;5;0m…orld\x1b[0m"
+        );
+        assert_eq!(
+            truncate_str_ansi("\x1b[1m\x1b[mhello world", 5),
+            "\x1b[1m\x1b[mhell…"
+        );
+    }
+
+    #[test]
+    fn test_ansi_with_options() {
+        // A Nerd Font branch icon, patched to be two columns wide.
+        let mut overrides = WidthOverrides::new();
+        overrides.insert_range('\u{e000}'..='\u{f8ff}', 2);
+        let options = WidthOptions::new().overrides(overrides);
+
+        let branch = "\x1b[32m\u{e0a0} main\x1b[0m";
+        assert_eq!(str_width_ansi(branch), 6);
+        assert_eq!(str_width_ansi_with(branch, &options), 7);
+
+        assert_eq!(truncate_str_ansi(branch, 6), branch);
+        assert_eq!(
+            truncate_str_ansi_with(branch, 6, &options),
+            "\x1b[32m\u{e0a0} ma…\x1b[0m"
+        );
+        assert_eq!(
+            truncate_str_leading_ansi_with(branch, 6, &options),
+            "\x1b[32m… main\x1b[0m"
+        );
+    }
+
+    #[test]
+    fn test_hyperlink_uri() {
+        assert_eq!(
+            hyperlink_uri("\x1b]8;;https://example.com\x1b\\"),
+            Some("https://example.com")
+        );
+        assert_eq!(
+            hyperlink_uri("\x1b]8;id=1;https://example.com\x07"),
+            Some("https://example.com")
+        );
+        assert_eq!(hyperlink_uri("\x1b]8;;\x1b\\"), Some(""));
+        assert_eq!(hyperlink_uri("\x1b]0;title\x07"), None);
+        assert_eq!(hyperlink_uri("\x1b[31m"), None);
+    }
+
+    #[test]
+    fn test_truncate_hyperlink() {
+        let link = "see \x1b]8;;https://example.com\x1b\\example.com\x1b]8;;\x1b\\ for more";
+
+        assert_eq!(str_width_ansi(link), 24);
+        assert_eq!(truncate_str_ansi(link, 24), link);
+        assert_eq!(
+            truncate_str_ansi(link, 16),
+            "see \x1b]8;;https://example.com\x1b\\example.com\x1b]8;;\x1b\\…"
+        );
+        assert_eq!(
+            truncate_str_ansi(link, 10),
+            "see \x1b]8;;https://example.com\x1b\\examp\x1b]8;;\x1b\\…",
+            "the hyperlink should be closed before the ellipsis"
+        );
+        assert_eq!(
+            truncate_str_ansi("\x1b]8;;https://example.com\x07example.com\x1b]8;;\x07", 5),
+            "\x1b]8;;https://example.com\x07exam\x1b]8;;\x07…",
+            "the hyperlink should be closed with the same terminator"
+        );
+    }
+
+    #[test]
+    fn test_truncate_hyperlink_leading() {
+        let link = "see \x1b]8;;https://example.com\x1b\\example.com\x1b]8;;\x1b\\ for more";
+
+        assert_eq!(truncate_str_leading_ansi(link, 24), link);
+        assert_eq!(
+            truncate_str_leading_ansi(link, 10),
+            "…\x1b]8;;\x1b\\ for more",
+            "the hyperlink should not be reopened just to be closed"
+        );
+        assert_eq!(
+            truncate_str_leading_ansi(link, 14),
+            "…\x1b]8;;https://example.com\x1b\\.com\x1b]8;;\x1b\\ for more",
+            "the hyperlink should be reopened after the ellipsis"
+        );
+    }
+}
@@ -0,0 +1,635 @@
+//! Working with display columns rather than bytes.
+
+use std::{borrow::Cow, ops::Range};
+
+use unicode_segmentation::{GraphemeIndices, UnicodeSegmentation};
+
+use crate::{
+    char_width_with, cut_sided, expand_tabs, str_width_with, take, take_at, Ellipsis, Measure,
+    WidthOptions,
+};
+
+/// The default options, as a static so that [`grapheme_columns`] can borrow them for as long as it
+/// needs.
+static DEFAULT_OPTIONS: WidthOptions = WidthOptions::DEFAULT;
+
+/// A grapheme alongside where it is displayed, yielded by [`grapheme_columns`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct GraphemeColumn {
+    /// The byte range of the grapheme.
+    pub bytes: Range<usize>,
+    /// The column the grapheme starts at.
+    pub column: usize,
+    /// The width of the grapheme.
+    pub width: usize,
+}
+
+/// An iterator over the graphemes of a string and the columns they are displayed at, returned by
+/// [`grapheme_columns`].
+#[derive(Clone, Debug)]
+pub struct GraphemeColumns<'a> {
+    graphemes: GraphemeIndices<'a>,
+    column: usize,
+    options: &'a WidthOptions,
+}
+
+impl Iterator for GraphemeColumns<'_> {
+    type Item = GraphemeColumn;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let (start, g) = self.graphemes.next()?;
+        let column = self.column;
+        let width = self.options.grapheme_width_at(g, column);
+        self.column += width;
+
+        Some(GraphemeColumn {
+            bytes: start..start + g.len(),
+            column,
+            width,
+        })
+    }
+}
+
+/// Returns an iterator over the [graphemes](https://www.unicode.org/reports/tr29/#Grapheme_Cluster_Boundaries)
+/// of `s`, alongside the columns they are displayed at.
+///
+/// ```
+/// use unicode_ellipsis::grapheme_columns;
+///
+/// let columns: Vec<_> = grapheme_columns("a施b")
+///     .map(|g| (g.bytes, g.column, g.width))
+///     .collect();
+/// assert_eq!(columns, [(0..1, 0, 1), (1..4, 1, 2), (4..5, 3, 1)]);
+/// ```
+#[inline]
+pub fn grapheme_columns(s: &str) -> GraphemeColumns<'_> {
+    grapheme_columns_with(s, &DEFAULT_OPTIONS)
+}
+
+/// Returns an iterator over the graphemes of `s` and the columns they are displayed at like
+/// [`grapheme_columns`], measured with the given [`WidthOptions`].
+pub fn grapheme_columns_with<'a>(s: &'a str, options: &'a WidthOptions) -> GraphemeColumns<'a> {
+    GraphemeColumns {
+        graphemes: s.grapheme_indices(true),
+        column: 0,
+        options,
+    }
+}
+
+/// Returns the column that the grapheme at byte offset `byte` of `s` starts at. If `byte` is in
+/// the middle of a grapheme, this is where that grapheme starts, and if it is past the end of `s`,
+/// this is the width of `s`.
+///
+/// ```
+/// use unicode_ellipsis::column_at_byte;
+///
+/// assert_eq!(column_at_byte("a施b", 1), 1);
+/// assert_eq!(column_at_byte("a施b", 4), 3);
+/// assert_eq!(column_at_byte("a施b", 2), 1);
+/// ```
+#[inline]
+pub fn column_at_byte(s: &str, byte: usize) -> usize {
+    column_at_byte_with(s, byte, &WidthOptions::DEFAULT)
+}
+
+/// Returns the column that the grapheme at byte offset `byte` of `s` starts at like
+/// [`column_at_byte`], measured with the given [`WidthOptions`].
+pub fn column_at_byte_with(s: &str, byte: usize, options: &WidthOptions) -> usize {
+    let mut end = 0;
+
+    for g in grapheme_columns_with(s, options) {
+        if g.bytes.end > byte {
+            return g.column;
+        }
+        end = g.column + g.width;
+    }
+
+    end
+}
+
+/// How a column was mapped to a byte offset by [`byte_at_column`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Snap {
+    /// The column is exactly where a grapheme starts.
+    Exact,
+    /// The column is in the middle of a grapheme or past the end of the string, so the byte offset
+    /// is for the nearest grapheme boundary to its left.
+    Left,
+    /// The column is in the middle of a grapheme, so the byte offset is for the nearest grapheme
+    /// boundary to its right.
+    Right,
+}
+
+/// Returns the byte offset of the grapheme that starts at column `col` of `s`, such as for placing
+/// a cursor where a mouse was clicked.
+///
+/// If `col` is in the middle of a grapheme, like the second column of a wide character, this
+/// returns the nearest grapheme boundary instead, preferring the one to the left when both are as
+/// near. If `col` is past the end of `s`, this returns the length of `s`.
+///
+/// ```
+/// use unicode_ellipsis::{byte_at_column, Snap};
+///
+/// assert_eq!(byte_at_column("a施b", 1), (1, Snap::Exact));
+/// assert_eq!(byte_at_column("a施b", 2), (1, Snap::Left));
+/// assert_eq!(byte_at_column("a施b", 3), (4, Snap::Exact));
+/// assert_eq!(byte_at_column("a\tb", 7), (2, Snap::Right));
+/// ```
+#[inline]
+pub fn byte_at_column(s: &str, col: usize) -> (usize, Snap) {
+    byte_at_column_with(s, col, &WidthOptions::DEFAULT)
+}
+
+/// Returns the byte offset of the grapheme that starts at column `col` of `s` like
+/// [`byte_at_column`], measured with the given [`WidthOptions`].
+pub fn byte_at_column_with(s: &str, col: usize, options: &WidthOptions) -> (usize, Snap) {
+    let taken = take::<false, _>(s, col, options);
+
+    if taken.width == col {
+        return (taken.bytes, Snap::Exact);
+    }
+
+    // The next grapheme straddles `col`, or `s` ends before it.
+    match s[taken.bytes..].graphemes(true).next() {
+        Some(g) => {
+            let end = taken.width + options.grapheme_width_at(g, taken.width);
+
+            if col - taken.width <= end - col {
+                (taken.bytes, Snap::Left)
+            } else {
+                (taken.bytes + g.len(), Snap::Right)
+            }
+        }
+        None => (s.len(), Snap::Left),
+    }
+}
+
+/// A range of columns of a string, returned by [`slice_columns`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ColumnSlice<'a> {
+    /// The columns to display.
+    pub output: Cow<'a, str>,
+    /// The byte range of the original string shown in the output. This doesn't include graphemes
+    /// that were only partly visible and replaced with padding.
+    pub range: Range<usize>,
+}
+
+/// Returns the columns `start_col..start_col + width` of `content`, such as for scrolling a long
+/// line horizontally.
+///
+/// If anything before or after those columns is cut off, that side is marked with an ellipsis
+/// character. A grapheme that is only partly visible, like a wide character straddling either
+/// edge, is replaced with spaces. Tabs are measured from the start of `content`, and are replaced
+/// with spaces so they still line up wherever the output is displayed.
+///
+/// ```
+/// use unicode_ellipsis::slice_columns;
+///
+/// let line = "施氏食獅史 is a poem";
+///
+/// let slice = slice_columns(line, 3, 6);
+/// assert_eq!(slice.output, "…食獅…");
+/// assert_eq!(&line[slice.range], "食獅");
+///
+/// // `氏` straddles the left edge, and `獅` the right edge.
+/// assert_eq!(slice_columns(line, 2, 6).output, "… 食 …");
+/// ```
+#[inline]
+pub fn slice_columns(content: &str, start_col: usize, width: usize) -> ColumnSlice<'_> {
+    slice_columns_with(content, start_col, width, &WidthOptions::DEFAULT)
+}
+
+/// Returns the columns `start_col..start_col + width` of `content` like [`slice_columns`],
+/// measured with the given [`WidthOptions`].
+pub fn slice_columns_with<'a>(
+    content: &'a str,
+    start_col: usize,
+    width: usize,
+    options: &WidthOptions,
+) -> ColumnSlice<'a> {
+    let marker = Ellipsis::new(Ellipsis::DEFAULT.text, options);
+    let end_col = start_col.saturating_add(width);
+
+    let before = take::<false, _>(content, start_col, options);
+    if width == 0 || before.bytes == content.len() {
+        return ColumnSlice {
+            output: "".into(),
+            range: before.bytes..before.bytes,
+        };
+    }
+
+    let cut_start = start_col > 0;
+    let cut_end = take::<false, _>(content, end_col, options).bytes < content.len();
+
+    // The columns left for the content between the markers.
+    let left = start_col + if cut_start { marker.width } else { 0 };
+    let right = end_col.saturating_sub(if cut_end { marker.width } else { 0 });
+
+    if left > right {
+        // Only room for a single marker.
+        return ColumnSlice {
+            output: marker.fit(width, options).into(),
+            range: before.bytes..before.bytes,
+        };
+    }
+
+    // Skip everything before the left edge, padding over a grapheme that straddles it.
+    let skipped = take::<false, _>(content, left, options);
+    let mut start = skipped.bytes;
+    let mut column = skipped.width;
+    let mut pad_left = 0;
+
+    if column < left {
+        if let Some(g) = content[start..].graphemes(true).next() {
+            let g_end = column + options.grapheme_width_at(g, column);
+            pad_left = g_end.min(right) - left;
+            start += g.len();
+            column = g_end;
+        }
+    }
+
+    let kept = take_at::<false, _>(
+        &content[start..],
+        column,
+        right.saturating_sub(column),
+        options,
+    );
+    let end = start + kept.bytes;
+    let pad_right = if cut_end {
+        right.saturating_sub(column + kept.width)
+    } else {
+        0
+    };
+
+    let text = &content[start..end];
+    let output = if !cut_start && !cut_end && pad_left == 0 && !text.contains('\t') {
+        text.into()
+    } else {
+        let text = if text.contains('\t') {
+            expand_tabs(text, column, options).into()
+        } else {
+            Cow::Borrowed(text)
+        };
+
+        let mut ret =
+            String::with_capacity(text.len() + pad_left + pad_right + 2 * marker.text.len());
+        if cut_start {
+            ret.push_str(marker.text);
+        }
+        ret.extend(std::iter::repeat_n(' ', pad_left));
+        ret.push_str(&text);
+        ret.extend(std::iter::repeat_n(' ', pad_right));
+        if cut_end {
+            ret.push_str(marker.text);
+        }
+
+        ret.into()
+    };
+
+    ColumnSlice {
+        output,
+        range: start..end,
+    }
+}
+
+/// Where a string is placed when it is padded, for use with [`fit_str`].
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum Align {
+    /// Pad the end of the string.
+    #[default]
+    Left,
+    /// Pad the start of the string.
+    Right,
+    /// Pad both sides of the string evenly. If the padding can't be split evenly, the extra
+    /// column goes at the end.
+    Center,
+}
+
+/// Truncates `content` like [`truncate_str`](crate::truncate_str) if it is too wide, and pads it
+/// with spaces otherwise, so that it is exactly `width` columns wide. This includes when a wide
+/// character doesn't fit in the last column before the ellipsis.
+///
+/// If the start of the string is padded, any tabs in it are replaced with spaces so that they
+/// still take up the same width.
+///
+/// ```
+/// use unicode_ellipsis::{fit_str, Align};
+///
+/// assert_eq!(fit_str("name", 6, Align::Left), "name  ");
+/// assert_eq!(fit_str("name", 6, Align::Right), "  name");
+/// assert_eq!(fit_str("施氏食獅史", 6, Align::Left), "施氏… ");
+/// ```
+#[inline]
+pub fn fit_str(content: &str, width: usize, align: Align) -> Cow<'_, str> {
+    fit_str_with_fill(content, width, align, ' ')
+}
+
+/// Like [`fit_str`], but pads with `fill` rather than spaces. If `fill` isn't exactly one column
+/// wide, spaces are used instead.
+///
+/// ```
+/// use unicode_ellipsis::{fit_str_with_fill, Align};
+///
+/// assert_eq!(fit_str_with_fill("title", 9, Align::Center, '─'), "──title──");
+/// ```
+#[inline]
+pub fn fit_str_with_fill(content: &str, width: usize, align: Align, fill: char) -> Cow<'_, str> {
+    fit_str_with(content, width, align, fill, &WidthOptions::DEFAULT)
+}
+
+/// Like [`fit_str_with_fill`], but measured with the given [`WidthOptions`].
+pub fn fit_str_with<'a>(
+    content: &'a str,
+    width: usize,
+    align: Align,
+    fill: char,
+    options: &WidthOptions,
+) -> Cow<'a, str> {
+    let ellipsis = Ellipsis::new(Ellipsis::DEFAULT.text, options);
+
+    let (output, output_width) = match cut_sided::<false, _>(content, width, ellipsis, options) {
+        Some(cut) => (
+            cut.join(content, ellipsis, width, options).into(),
+            // An ellipsis wider than `width` is left out entirely.
+            cut.head.width
+                + if ellipsis.width <= width {
+                    ellipsis.width
+                } else {
+                    0
+                },
+        ),
+        None => (Cow::Borrowed(content), str_width_with(content, options)),
+    };
+
+    let padding = width.saturating_sub(output_width);
+    if padding == 0 {
+        return output;
+    }
+
+    let fill = if char_width_with(fill, options) == 1 {
+        fill
+    } else {
+        ' '
+    };
+    let (before, after) = match align {
+        Align::Left => (0, padding),
+        Align::Right => (padding, 0),
+        Align::Center => (padding / 2, padding - padding / 2),
+    };
+
+    // Padding the start moves where tabs start, so expand them to keep the same width.
+    let output = if before > 0 && output.contains('\t') {
+        expand_tabs(&output, 0, options).into()
+    } else {
+        output
+    };
+
+    let mut ret = String::with_capacity(output.len() + padding * fill.len_utf8());
+    ret.extend(std::iter::repeat_n(fill, before));
+    ret.push_str(&output);
+    ret.extend(std::iter::repeat_n(fill, after));
+
+    ret.into()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{str_width, CellWidth, WidthOverrides};
+
+    #[test]
+    fn test_grapheme_columns() {
+        let columns: Vec<_> = grapheme_columns("a\t🇨🇦e\u{301}").collect();
+        assert_eq!(
+            columns,
+            [
+                GraphemeColumn {
+                    bytes: 0..1,
+                    column: 0,
+                    width: 1
+                },
+                GraphemeColumn {
+                    bytes: 1..2,
+                    column: 1,
+                    width: 7
+                },
+                GraphemeColumn {
+                    bytes: 2..10,
+                    column: 8,
+                    width: 2
+                },
+                GraphemeColumn {
+                    bytes: 10..13,
+                    column: 10,
+                    width: 1
+                },
+            ]
+        );
+        assert_eq!(grapheme_columns("").next(), None);
+    }
+
+    #[test]
+    fn test_column_at_byte() {
+        let s = "a\t🇨🇦e\u{301}";
+        assert_eq!(column_at_byte(s, 0), 0);
+        assert_eq!(column_at_byte(s, 2), 8);
+        assert_eq!(column_at_byte(s, 6), 8);
+        assert_eq!(column_at_byte(s, 10), 10);
+        assert_eq!(column_at_byte(s, 11), 10);
+        assert_eq!(column_at_byte(s, 13), 11);
+        assert_eq!(column_at_byte(s, 100), 11);
+        assert_eq!(column_at_byte("", 0), 0);
+    }
+
+    #[test]
+    fn test_byte_at_column() {
+        let s = "a\t🇨🇦e\u{301}";
+        assert_eq!(byte_at_column(s, 0), (0, Snap::Exact));
+        assert_eq!(byte_at_column(s, 1), (1, Snap::Exact));
+        assert_eq!(byte_at_column(s, 4), (1, Snap::Left));
+        assert_eq!(byte_at_column(s, 5), (2, Snap::Right));
+        assert_eq!(byte_at_column(s, 8), (2, Snap::Exact));
+        assert_eq!(byte_at_column(s, 9), (2, Snap::Left));
+        assert_eq!(byte_at_column(s, 10), (10, Snap::Exact));
+        assert_eq!(byte_at_column(s, 11), (13, Snap::Exact));
+        assert_eq!(byte_at_column(s, 12), (13, Snap::Left));
+        assert_eq!(byte_at_column("", 3), (0, Snap::Left));
+
+        for g in grapheme_columns(s) {
+            assert_eq!(byte_at_column(s, g.column), (g.bytes.start, Snap::Exact));
+            assert_eq!(column_at_byte(s, g.bytes.start), g.column);
+        }
+    }
+
+    #[test]
+    fn test_columns_with() {
+        let s = "a\u{e0a0}b";
+        let mut overrides = WidthOverrides::new();
+        overrides.insert_char('\u{e0a0}', 2);
+        let options = WidthOptions::new().overrides(overrides);
+
+        let columns: Vec<_> = grapheme_columns_with(s, &options)
+            .map(|g| (g.column, g.width))
+            .collect();
+        assert_eq!(columns, [(0, 1), (1, 2), (3, 1)]);
+
+        assert_eq!(column_at_byte(s, 4), 2);
+        assert_eq!(column_at_byte_with(s, 4, &options), 3);
+        assert_eq!(byte_at_column(s, 2), (4, Snap::Exact));
+        assert_eq!(byte_at_column_with(s, 2, &options), (1, Snap::Left));
+        assert_eq!(byte_at_column_with(s, 3, &options), (4, Snap::Exact));
+    }
+
+    #[test]
+    fn test_slice_columns() {
+        let line = "hello world";
+        assert_eq!(slice_columns(line, 0, 11).output, "hello world");
+        assert_eq!(slice_columns(line, 0, 20).output, "hello world");
+        assert_eq!(slice_columns(line, 0, 5).output, "hell…");
+        assert_eq!(slice_columns(line, 6, 5).output, "…orld");
+        assert_eq!(slice_columns(line, 3, 5).output, "…o w…");
+        assert_eq!(slice_columns(line, 3, 1).output, "…");
+        assert_eq!(slice_columns(line, 3, 0).output, "");
+        assert_eq!(slice_columns(line, 11, 5).output, "");
+        assert_eq!(slice_columns(line, 20, 5).output, "");
+
+        let slice = slice_columns(line, 3, 5);
+        assert_eq!(slice.range, 4..7);
+        assert!(matches!(
+            slice_columns(line, 0, 11).output,
+            Cow::Borrowed(_)
+        ));
+    }
+
+    #[test]
+    fn test_slice_columns_wide() {
+        let line = "施氏食獅史";
+        assert_eq!(slice_columns(line, 0, 10).output, line);
+        assert_eq!(slice_columns(line, 0, 5).output, "施氏…");
+        assert_eq!(slice_columns(line, 0, 4).output, "施 …");
+        assert_eq!(slice_columns(line, 1, 5).output, "…氏 …");
+        assert_eq!(slice_columns(line, 5, 5).output, "…獅史");
+        assert_eq!(slice_columns(line, 2, 2).output, "……");
+
+        let slice = slice_columns(line, 1, 5);
+        assert_eq!(slice.range, 3..6);
+
+        // `氏` straddles the left edge.
+        assert_eq!(slice_columns(line, 2, 3).output, "… …");
+        assert_eq!(slice_columns(line, 2, 3).range, 6..6);
+
+        // `氏` is wider than the space left between the markers.
+        assert_eq!(slice_columns(line, 1, 3).output, "… …");
+    }
+
+    #[test]
+    fn test_slice_columns_with() {
+        // `…` is ambiguous, so the markers are two columns wide in a CJK terminal.
+        let options = WidthOptions::new().ambiguous(CellWidth::Wide);
+
+        let line = "abcdefgh";
+        assert_eq!(slice_columns(line, 2, 5).output, "…def…");
+        assert_eq!(slice_columns_with(line, 0, 8, &options).output, line);
+        assert_eq!(slice_columns_with(line, 0, 5, &options).output, "abc…");
+
+        let slice = slice_columns_with(line, 2, 5, &options);
+        assert_eq!(slice.output, "…e…");
+        assert_eq!(&line[slice.range], "e");
+
+        // Only room for a single marker, or not even that.
+        assert_eq!(slice_columns_with(line, 2, 3, &options).output, "…");
+        assert_eq!(slice_columns_with(line, 2, 1, &options).output, "");
+
+        // The left marker covers most of `氏`, so the rest of it is padded.
+        let line = "施氏食獅史";
+        assert_eq!(slice_columns_with(line, 1, 6, &options).output, "…  …");
+        assert_eq!(slice_columns_with(line, 1, 7, &options).output, "… 食…");
+    }
+
+    #[test]
+    fn test_slice_columns_tabs() {
+        let line = "a\tb\tc";
+        assert_eq!(slice_columns(line, 0, 20).output, "a       b       c");
+        assert_eq!(slice_columns(line, 4, 6).output, "…   b…");
+        assert_eq!(slice_columns(line, 8, 9).output, "…       c");
+        assert_eq!(slice_columns(line, 8, 9).range, 3..5);
+    }
+
+    #[test]
+    fn test_fit_str() {
+        assert_eq!(fit_str("abc", 5, Align::Left), "abc  ");
+        assert_eq!(fit_str("abc", 5, Align::Right), "  abc");
+        assert_eq!(fit_str("abc", 6, Align::Center), " abc  ");
+        assert_eq!(fit_str("abc", 3, Align::Center), "abc");
+        assert_eq!(fit_str("abcdef", 4, Align::Right), "abc…");
+        assert_eq!(fit_str("", 2, Align::Left), "  ");
+        assert_eq!(fit_str("abc", 0, Align::Left), "");
+        assert!(matches!(fit_str("abc", 3, Align::Left), Cow::Borrowed(_)));
+
+        // The wide character doesn't fit in the last column before the ellipsis.
+        assert_eq!(fit_str("施氏食獅史", 4, Align::Left), "施… ");
+        assert_eq!(fit_str("施氏食獅史", 4, Align::Right), " 施…");
+        assert_eq!(fit_str("施氏", 3, Align::Center), "施…");
+        assert_eq!(fit_str("施氏", 5, Align::Center), "施氏 ");
+
+        assert_eq!(fit_str("a\tb", 10, Align::Left), "a\tb ");
+        assert_eq!(fit_str("a\tb", 10, Align::Right), " a       b");
+
+        for content in ["", "a", "施氏食獅史", "🇨🇦🇨🇦🇨🇦", "a\tb"] {
+            for width in 0..12 {
+                for align in [Align::Left, Align::Right, Align::Center] {
+                    assert_eq!(
+                        str_width(&fit_str(content, width, align)),
+                        width,
+                        "{content:?} {width} {align:?}"
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_fit_str_with_fill() {
+        assert_eq!(fit_str_with_fill("ab", 5, Align::Left, '.'), "ab...");
+        assert_eq!(fit_str_with_fill("ab", 5, Align::Center, '─'), "─ab──");
+        assert_eq!(fit_str_with_fill("ab", 4, Align::Right, '施'), "  ab");
+        assert_eq!(fit_str_with_fill("ab", 4, Align::Right, '\u{301}'), "  ab");
+    }
+
+    #[test]
+    fn test_fit_str_with() {
+        let options = WidthOptions::new().ambiguous(CellWidth::Wide);
+
+        // Box drawing chars are ambiguous, so `─` can't pad single columns in a CJK terminal.
+        assert_eq!(
+            fit_str_with_fill("title", 9, Align::Center, '─'),
+            "──title──"
+        );
+        assert_eq!(
+            fit_str_with("title", 9, Align::Center, '─', &options),
+            "  title  "
+        );
+
+        // The ellipsis is two columns wide too, and is padded over when it doesn't fit.
+        assert_eq!(
+            fit_str_with("abcdef", 5, Align::Left, ' ', &options),
+            "abc…"
+        );
+        assert_eq!(fit_str_with("abcdef", 1, Align::Left, ' ', &options), " ");
+
+        for content in ["", "a", "±±±±", "施氏食獅史", "a\tb"] {
+            for width in 0..12 {
+                for align in [Align::Left, Align::Right, Align::Center] {
+                    assert_eq!(
+                        str_width_with(
+                            &fit_str_with(content, width, align, '.', &options),
+                            &options
+                        ),
+                        width,
+                        "{content:?} {width} {align:?}"
+                    );
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,139 @@
+//! Showing control characters, which terminals don't display.
+
+use std::borrow::Cow;
+
+use crate::Measure;
+
+/// How [control characters](https://www.unicode.org/charts/PDF/U0000.pdf) like carriage returns
+/// or escapes are shown, for use with [`Truncator::control_chars`](crate::Truncator::control_chars).
+///
+/// Terminals don't display control characters, and some of them move the cursor or start escape
+/// sequences instead, so text containing them can end up wider or narrower than measured. Replacing
+/// them with something visible avoids this, and the replacement is measured instead.
+///
+/// This applies to the C0 control characters, `DEL` and the C1 control characters, except for tabs,
+/// which are measured by tab stops instead. C1 control characters have no caret notation or control
+/// picture, so they are always shown as escapes.
+///
+/// ```
+/// use unicode_ellipsis::ControlChars;
+///
+/// assert_eq!(ControlChars::Caret.visualize("a\r\nb"), "a^M^Jb");
+/// assert_eq!(ControlChars::Pictures.visualize("a\r\nb"), "a␍␊b");
+/// assert_eq!(ControlChars::Escape.visualize("a\r\nb"), "a\\u{d}\\u{a}b");
+/// assert_eq!(ControlChars::Keep.visualize("a\r\nb"), "a\r\nb");
+/// ```
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum ControlChars {
+    /// Keep control characters as they are, measuring them like any other character.
+    #[default]
+    Keep,
+    /// Replace control characters with [caret notation](https://en.wikipedia.org/wiki/Caret_notation),
+    /// like `^M` for a carriage return.
+    Caret,
+    /// Replace control characters with [control pictures](https://www.unicode.org/charts/PDF/U2400.pdf),
+    /// like `␍` for a carriage return.
+    Pictures,
+    /// Replace control characters with escapes, like `\u{d}` for a carriage return.
+    Escape,
+}
+
+impl ControlChars {
+    /// Returns `s` with any control characters replaced.
+    pub fn visualize(self, s: &str) -> Cow<'_, str> {
+        if self == ControlChars::Keep || !s.chars().any(is_replaced) {
+            return s.into();
+        }
+
+        let mut ret = String::with_capacity(s.len() + 8);
+        for c in s.chars() {
+            if is_replaced(c) {
+                self.push_replacement(c, &mut ret);
+            } else {
+                ret.push(c);
+            }
+        }
+
+        ret.into()
+    }
+
+    /// Pushes what replaces the control character `c` onto `out`.
+    fn push_replacement(self, c: char, out: &mut String) {
+        match (self, u32::from(c)) {
+            (ControlChars::Caret, code @ (0..=0x1f | 0x7f)) => {
+                out.push('^');
+                out.push(char::from(code as u8 ^ 0x40));
+            }
+            (ControlChars::Pictures, code @ 0..=0x1f) => {
+                out.push(char::from_u32(0x2400 + code).unwrap_or(char::REPLACEMENT_CHARACTER));
+            }
+            (ControlChars::Pictures, 0x7f) => out.push('␡'),
+            _ => out.extend(c.escape_unicode()),
+        }
+    }
+}
+
+/// Whether `c` is a control character that is replaced, rather than kept as is.
+#[inline]
+fn is_replaced(c: char) -> bool {
+    c.is_control() && c != '\t'
+}
+
+/// Measures like `measure`, but measures control characters by what replaces them.
+#[derive(Clone, Copy, Debug)]
+pub(crate) struct Visible<M> {
+    pub(crate) measure: M,
+    pub(crate) control_chars: ControlChars,
+}
+
+impl<M: Measure> Measure for Visible<M> {
+    type Width = M::Width;
+
+    #[inline]
+    fn grapheme_width(&self, grapheme: &str) -> Self::Width {
+        match self.control_chars.visualize(grapheme) {
+            Cow::Borrowed(grapheme) => self.measure.grapheme_width(grapheme),
+            Cow::Owned(replaced) => self.measure.str_width(&replaced),
+        }
+    }
+
+    #[inline]
+    fn grapheme_width_at(&self, grapheme: &str, column: Self::Width) -> Self::Width {
+        match self.control_chars.visualize(grapheme) {
+            Cow::Borrowed(grapheme) => self.measure.grapheme_width_at(grapheme, column),
+            Cow::Owned(replaced) => self.measure.str_width(&replaced),
+        }
+    }
+
+    #[inline]
+    fn is_byte_bounded(&self) -> bool {
+        // Replacements are wider than the control characters they replace.
+        self.control_chars == ControlChars::Keep && self.measure.is_byte_bounded()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_visualize() {
+        assert_eq!(ControlChars::Caret.visualize("\0\x1b\x7f"), "^@^[^?");
+        assert_eq!(ControlChars::Pictures.visualize("\0\x1b\x7f"), "␀␛␡");
+        assert_eq!(
+            ControlChars::Escape.visualize("\0\x1b\x7f"),
+            "\\u{0}\\u{1b}\\u{7f}"
+        );
+
+        // C1 control characters are always escaped.
+        assert_eq!(ControlChars::Caret.visualize("a\u{9b}b"), "a\\u{9b}b");
+        assert_eq!(ControlChars::Pictures.visualize("a\u{85}b"), "a\\u{85}b");
+
+        // Tabs and other text are left alone.
+        assert!(matches!(
+            ControlChars::Caret.visualize("a\tb 施"),
+            Cow::Borrowed("a\tb 施")
+        ));
+    }
+}
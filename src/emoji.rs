@@ -0,0 +1,117 @@
+//! Emoji properties that affect width.
+
+use crate::widecharwidth::in_table;
+
+/// Characters with the [Extended_Pictographic](https://www.unicode.org/reports/tr51/#Emoji_Properties)
+/// property, taken from `unicode-segmentation`.
+const EXTENDED_PICTOGRAPHIC_TABLE: &[(u32, u32)] = &[
+    (0x000A9, 0x000A9),
+    (0x000AE, 0x000AE),
+    (0x0203C, 0x0203C),
+    (0x02049, 0x02049),
+    (0x02122, 0x02122),
+    (0x02139, 0x02139),
+    (0x02194, 0x02199),
+    (0x021A9, 0x021AA),
+    (0x0231A, 0x0231B),
+    (0x02328, 0x02328),
+    (0x02388, 0x02388),
+    (0x023CF, 0x023CF),
+    (0x023E9, 0x023F3),
+    (0x023F8, 0x023FA),
+    (0x024C2, 0x024C2),
+    (0x025AA, 0x025AB),
+    (0x025B6, 0x025B6),
+    (0x025C0, 0x025C0),
+    (0x025FB, 0x025FE),
+    (0x02600, 0x02605),
+    (0x02607, 0x02612),
+    (0x02614, 0x02685),
+    (0x02690, 0x02705),
+    (0x02708, 0x02712),
+    (0x02714, 0x02714),
+    (0x02716, 0x02716),
+    (0x0271D, 0x0271D),
+    (0x02721, 0x02721),
+    (0x02728, 0x02728),
+    (0x02733, 0x02734),
+    (0x02744, 0x02744),
+    (0x02747, 0x02747),
+    (0x0274C, 0x0274C),
+    (0x0274E, 0x0274E),
+    (0x02753, 0x02755),
+    (0x02757, 0x02757),
+    (0x02763, 0x02767),
+    (0x02795, 0x02797),
+    (0x027A1, 0x027A1),
+    (0x027B0, 0x027B0),
+    (0x027BF, 0x027BF),
+    (0x02934, 0x02935),
+    (0x02B05, 0x02B07),
+    (0x02B1B, 0x02B1C),
+    (0x02B50, 0x02B50),
+    (0x02B55, 0x02B55),
+    (0x03030, 0x03030),
+    (0x0303D, 0x0303D),
+    (0x03297, 0x03297),
+    (0x03299, 0x03299),
+    (0x1F000, 0x1F0FF),
+    (0x1F10D, 0x1F10F),
+    (0x1F12F, 0x1F12F),
+    (0x1F16C, 0x1F171),
+    (0x1F17E, 0x1F17F),
+    (0x1F18E, 0x1F18E),
+    (0x1F191, 0x1F19A),
+    (0x1F1AD, 0x1F1E5),
+    (0x1F201, 0x1F20F),
+    (0x1F21A, 0x1F21A),
+    (0x1F22F, 0x1F22F),
+    (0x1F232, 0x1F23A),
+    (0x1F23C, 0x1F23F),
+    (0x1F249, 0x1F3FA),
+    (0x1F400, 0x1F53D),
+    (0x1F546, 0x1F64F),
+    (0x1F680, 0x1F6FF),
+    (0x1F774, 0x1F77F),
+    (0x1F7D5, 0x1F7FF),
+    (0x1F80C, 0x1F80F),
+    (0x1F848, 0x1F84F),
+    (0x1F85A, 0x1F85F),
+    (0x1F888, 0x1F88F),
+    (0x1F8AE, 0x1F8FF),
+    (0x1F90C, 0x1F93A),
+    (0x1F93C, 0x1F945),
+    (0x1F947, 0x1FAFF),
+    (0x1FC00, 0x1FFFD),
+];
+
+/// Returns whether a char `c` is a pictograph, which includes every emoji other than things like
+/// keycap bases and regional indicators.
+#[inline]
+pub(crate) fn is_extended_pictographic(c: char) -> bool {
+    // Every Extended_Pictographic char is at least U+00A9.
+    !c.is_ascii() && in_table(EXTENDED_PICTOGRAPHIC_TABLE, c as u32)
+}
+
+/// Returns whether a char `c` can be the start of a keycap sequence like `1️⃣`.
+#[inline]
+pub(crate) fn is_keycap_base(c: char) -> bool {
+    matches!(c, '0'..='9' | '#' | '*')
+}
+
+/// Returns whether a grapheme `g` is an emoji [ZWJ sequence](https://www.unicode.org/reports/tr51/#Emoji_ZWJ_Sequences)
+/// like `👩‍🔬`, where each part joined by a zero width joiner is a pictograph. Other scripts also
+/// use zero width joiners, such as Malayalam for its chillu letters.
+///
+/// Empty parts, like after a trailing joiner, are ignored.
+#[inline]
+pub(crate) fn is_emoji_zwj_sequence(g: &str) -> bool {
+    let starts_with_pictograph =
+        |part: &str| part.chars().next().is_some_and(is_extended_pictographic);
+
+    let mut parts = g.split('\u{200d}');
+    parts.next().is_some_and(starts_with_pictograph) && {
+        let mut joined = parts.filter(|part| !part.is_empty()).peekable();
+        joined.peek().is_some() && joined.all(starts_with_pictograph)
+    }
+}
@@ -2,122 +2,183 @@
 //!
 //! Additionally contains some helper functions regarding string and grapheme width.
 
+mod ansi;
+pub use ansi::*;
+
+mod columns;
+pub use columns::*;
+
+mod control;
+pub use control::*;
+
+mod emoji;
+
+mod measure;
+pub use measure::*;
+
+mod overrides;
+pub use overrides::*;
+
+mod path;
+pub use path::*;
+
+#[cfg(feature = "probe")]
+mod probe;
+#[cfg(feature = "probe")]
+pub use probe::*;
+
+mod profile;
+pub use profile::*;
+
+mod truncator;
+pub use truncator::*;
+
 mod width;
 pub use width::*;
 
-#[cfg(feature = "fish")]
+mod wrap;
+pub use wrap::*;
+
 mod widecharwidth;
+pub use widecharwidth::{char_width_class, CharWidthClass};
 
-use std::{borrow::Cow, num::NonZeroUsize};
+use std::{borrow::Cow, ops::Range};
 
 use unicode_segmentation::UnicodeSegmentation;
 
-enum AsciiIterationResult {
-    Complete(String),
-    Remaining(usize),
+/// A run of graphemes taken from one end of a string.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+struct Taken<W = usize> {
+    /// The number of bytes taken.
+    bytes: usize,
+    /// The width of the taken bytes.
+    width: W,
 }
 
-macro_rules! add_ellipsis {
-    ($text:expr) => {{
-        const SIZE_OF_ELLIPSIS: usize = 3;
-        let mut ret = String::with_capacity($text.len() + SIZE_OF_ELLIPSIS);
+/// An ellipsis string alongside its width.
+#[derive(Clone, Copy, Debug)]
+struct Ellipsis<'a, W = usize> {
+    text: &'a str,
+    width: W,
+}
 
-        if REVERSE {
-            ret.push('…');
+impl Ellipsis<'static> {
+    /// The default ellipsis, `…`.
+    const DEFAULT: Ellipsis<'static> = Ellipsis {
+        text: "…",
+        width: 1,
+    };
+}
+
+impl<'a, W: WidthUnit> Ellipsis<'a, W> {
+    #[inline]
+    fn new<M: Measure<Width = W> + ?Sized>(text: &'a str, measure: &M) -> Self {
+        Self {
+            text,
+            width: measure.str_width(text),
         }
+    }
 
-        ret.push_str($text);
+    /// Returns the ellipsis if it fits in `width`, otherwise as much of it as fits.
+    #[inline]
+    fn fit<M: Measure<Width = W> + ?Sized>(&self, width: W, measure: &M) -> &'a str {
+        if self.width <= width {
+            self.text
+        } else {
+            let taken = take::<false, M>(self.text, width, measure);
 
-        if !REVERSE {
-            ret.push('…');
+            // SAFETY: The bytes taken are at a grapheme boundary within `self.text`.
+            unsafe { taken_slice::<false>(self.text, taken.bytes) }
         }
+    }
+}
 
-        ret
-    }};
+/// Returns the first `bytes` bytes of `content`, or the last `bytes` bytes if `REVERSE` is set.
+///
+/// # Safety
+///
+/// `bytes` must be at most `content.len()`, and must land on a character boundary when counted
+/// from the relevant end of `content`.
+#[inline]
+unsafe fn taken_slice<const REVERSE: bool>(content: &str, bytes: usize) -> &str {
+    unsafe {
+        if REVERSE {
+            content.get_unchecked(content.len() - bytes..)
+        } else {
+            content.get_unchecked(..bytes)
+        }
+    }
 }
 
-/// Greedily add characters to the output until a non-ASCII grapheme is found, or
-/// the output is `width` long.
+/// Returns everything in `content` *except* the first `bytes` bytes, or the last `bytes` bytes
+/// if `REVERSE` is set.
+///
+/// # Safety
+///
+/// Same as [`taken_slice`].
 #[inline]
-fn greedy_ascii_add<const REVERSE: bool>(
-    content: &str,
-    width: NonZeroUsize,
-) -> AsciiIterationResult {
-    let width: usize = width.into();
-    debug_assert!(width < content.len());
+unsafe fn untaken_slice<const REVERSE: bool>(content: &str, bytes: usize) -> &str {
+    unsafe {
+        if REVERSE {
+            content.get_unchecked(..content.len() - bytes)
+        } else {
+            content.get_unchecked(bytes..)
+        }
+    }
+}
 
+/// Greedily take bytes until a byte that isn't printable ASCII is found, or `width` bytes have been
+/// taken. Returns the number of bytes taken.
+///
+/// If the next byte is not ASCII, the last ASCII byte is given back, as it may be part of a
+/// grapheme with what follows, like the `1` in the keycap `1️⃣`.
+#[inline]
+fn greedy_ascii_add<const REVERSE: bool>(content: &str, width: usize) -> usize {
+    let bytes = content.as_bytes();
+    let limit = width.min(bytes.len());
     let mut bytes_consumed = 0;
 
-    macro_rules! current_byte {
-        () => {
-            if REVERSE {
-                content.as_bytes()[content.len() - 1 - bytes_consumed]
-            } else {
-                content.as_bytes()[bytes_consumed]
-            }
-        };
-    }
-
-    macro_rules! consumed_slice {
-        () => {
-            // SAFETY: The use of `get_unchecked` is safe here because
-            // (`bytes_consumed` < `width`) && (`width` < `content.len()`)
-            // and `bytes_consumed` is at an ascii boundary.
-            unsafe {
-                if REVERSE {
-                    content.get_unchecked(content.len() - bytes_consumed..)
-                } else {
-                    content.get_unchecked(..bytes_consumed)
-                }
-            }
+    while bytes_consumed < limit {
+        let current_byte = if REVERSE {
+            bytes[bytes.len() - 1 - bytes_consumed]
+        } else {
+            bytes[bytes_consumed]
         };
-    }
 
-    while bytes_consumed < width - 1 {
-        let current_byte = current_byte!();
-        if current_byte.is_ascii() {
+        if current_byte.is_ascii_graphic() || current_byte == b' ' {
             bytes_consumed += 1;
         } else {
-            debug_assert!(consumed_slice!().is_ascii());
-            return AsciiIterationResult::Remaining(bytes_consumed);
+            break;
         }
     }
 
-    // If we made it all the way through, then we probably hit the width limit.
-    debug_assert!(consumed_slice!().is_ascii());
-
-    if current_byte!().is_ascii() {
-        AsciiIterationResult::Complete(add_ellipsis!(consumed_slice!()))
+    let next_byte = if REVERSE {
+        bytes
+            .len()
+            .checked_sub(bytes_consumed + 1)
+            .map(|i| bytes[i])
     } else {
-        AsciiIterationResult::Remaining(bytes_consumed)
+        bytes.get(bytes_consumed).copied()
+    };
+
+    match next_byte {
+        Some(byte) if !byte.is_ascii() => bytes_consumed.saturating_sub(1),
+        _ => bytes_consumed,
     }
 }
 
-/// Handle the remaining characters in a [`&str`].
+/// Handle the remaining characters in a [`&str`], continuing on from what was already `taken`
+/// and adding graphemes while they fit in `width`. See [`take_at`] for what `column` is.
 #[inline]
-fn handle_remaining<const REVERSE: bool>(
+fn handle_remaining<const REVERSE: bool, M: Measure + ?Sized>(
     content: &str,
-    mut bytes_consumed: usize,
-    width: usize,
-) -> Cow<'_, str> {
-    // SAFETY: The use of `get_unchecked` is safe here because
-    // (`bytes_consumed` < `width`) && (`width` < `content.len()`)
-    // and `bytes_consumed` is at an ASCII boundary.
-    let content_remaining = unsafe {
-        if REVERSE {
-            content.get_unchecked(..=content.len() - 1 - bytes_consumed)
-        } else {
-            content.get_unchecked(bytes_consumed..)
-        }
-    };
-
-    let mut curr_width = bytes_consumed;
-    let mut exceeded_width = false;
-
-    // This tracks the length of the last added string - note this does NOT match the grapheme *width*.
-    // Since the previous characters are always ASCII, this is always initialized as 1, unless the string
-    // is empty.
-    let mut last_grapheme_len = if curr_width == 0 { 0 } else { 1 };
+    column: M::Width,
+    mut taken: Taken<M::Width>,
+    width: M::Width,
+    measure: &M,
+) -> Taken<M::Width> {
+    // SAFETY: `taken.bytes` is always at a grapheme boundary (or an ASCII boundary) within `content`.
+    let content_remaining = unsafe { untaken_slice::<REVERSE>(content, taken.bytes) };
 
     // Cases to handle:
     // - Completes adding the entire string.
@@ -127,14 +188,16 @@ fn handle_remaining<const REVERSE: bool>(
     macro_rules! measure_graphemes {
         ($graphemes:expr) => {
             for g in $graphemes {
-                let g_width = grapheme_width(g);
+                let g_width = if REVERSE {
+                    measure.grapheme_width(g)
+                } else {
+                    measure.grapheme_width_at(g, column + taken.width)
+                };
 
-                if curr_width + g_width <= width {
-                    curr_width += g_width;
-                    last_grapheme_len = g.len();
-                    bytes_consumed += last_grapheme_len;
+                if taken.width + g_width <= width {
+                    taken.width = taken.width + g_width;
+                    taken.bytes += g.len();
                 } else {
-                    exceeded_width = true;
                     break;
                 }
             }
@@ -149,70 +212,536 @@ fn handle_remaining<const REVERSE: bool>(
         measure_graphemes!(graphemes)
     }
 
-    macro_rules! consumed_slice {
-        () => {
-            // SAFETY: The use of `get_unchecked` is safe here because
-            // `bytes_consumed` is tracking the lengths of graphemes contained
-            // within `content` and `bytes_consumed` is at a grapheme boundary.
-            unsafe {
-                if REVERSE {
-                    content.get_unchecked(content.len() - bytes_consumed..)
-                } else {
-                    content.get_unchecked(..bytes_consumed)
-                }
-            }
-        };
+    taken
+}
+
+/// Takes the longest run of graphemes from the start (or the end, if `REVERSE` is set) of
+/// `content` that fits within `width`.
+#[inline]
+fn take<const REVERSE: bool, M: Measure + ?Sized>(
+    content: &str,
+    width: M::Width,
+    measure: &M,
+) -> Taken<M::Width> {
+    take_at::<REVERSE, M>(content, M::Width::default(), width, measure)
+}
+
+/// Like [`take`], where `content` starts `column` units into a line. This is only known when
+/// taking from the start, so if `REVERSE` is set, graphemes like tabs whose width depends on where
+/// they start are measured with [`Measure::grapheme_width`] instead.
+#[inline]
+fn take_at<const REVERSE: bool, M: Measure + ?Sized>(
+    content: &str,
+    column: M::Width,
+    width: M::Width,
+    measure: &M,
+) -> Taken<M::Width> {
+    // What we are essentially doing is optimizing for the case that
+    // most, if not all of the string is ASCII. As such:
+    // - Step through each byte until `width` is hit or we find a non-ASCII
+    //   byte.
+    // - If the byte is ASCII, then add it.
+    //
+    // Then continue on treating the rest as graphemes.
+    let bytes_consumed = if measure.is_byte_bounded() {
+        greedy_ascii_add::<REVERSE>(content, width.to_usize())
+    } else {
+        0
+    };
+
+    handle_remaining::<REVERSE, M>(
+        content,
+        column,
+        Taken {
+            bytes: bytes_consumed,
+            width: M::Width::from_usize(bytes_consumed),
+        },
+        width,
+        measure,
+    )
+}
+
+/// Returns whether all of `content` fits within `width`.
+#[inline]
+fn fits<M: Measure + ?Sized>(content: &str, width: M::Width, measure: &M) -> bool {
+    (measure.is_byte_bounded()
+        && M::Width::from_usize(content.len()) <= width
+        && !content.contains('\t'))
+        || take::<false, M>(content, width, measure).bytes == content.len()
+}
+
+/// Replaces each tab in `text` with spaces up to the next tab stop, where `text` starts `column`
+/// units into a line.
+fn expand_tabs<M: Measure + ?Sized>(text: &str, mut column: M::Width, measure: &M) -> String {
+    let mut ret = String::with_capacity(text.len());
+
+    for g in UnicodeSegmentation::graphemes(text, true) {
+        let width = measure.grapheme_width_at(g, column);
+        column = column + width;
+
+        if g == "\t" {
+            ret.extend(std::iter::repeat_n(' ', width.to_usize()));
+        } else {
+            ret.push_str(g);
+        }
+    }
+
+    ret
+}
+
+/// Which parts of a string are kept when it is truncated.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+struct Cut<W = usize> {
+    /// What is kept from the start of the string.
+    head: Taken<W>,
+    /// What is kept from the end of the string.
+    tail: Taken<W>,
+}
+
+impl<W: WidthUnit> Cut<W> {
+    /// Joins the kept parts of `content` with `ellipsis`, cutting down the ellipsis if it does not
+    /// fit in `width`.
+    fn join<M: Measure<Width = W> + ?Sized>(
+        &self,
+        content: &str,
+        ellipsis: Ellipsis<'_, W>,
+        width: W,
+        measure: &M,
+    ) -> String {
+        let (head_text, tail_text) = self.parts(content);
+        let ellipsis = ellipsis.fit(width, measure);
+
+        let mut ret = String::with_capacity(head_text.len() + ellipsis.len() + tail_text.len());
+        ret.push_str(head_text);
+        ret.push_str(ellipsis);
+        ret.push_str(tail_text);
+
+        ret
     }
 
-    if exceeded_width {
-        if curr_width == width {
-            // Remove the last consumed grapheme cluster.
-            bytes_consumed -= last_grapheme_len;
+    /// Returns the parts of `content` kept at the start and at the end.
+    #[inline]
+    fn parts<'a>(&self, content: &'a str) -> (&'a str, &'a str) {
+        // SAFETY: Both `head.bytes` and `tail.bytes` are at grapheme boundaries within `content`.
+        unsafe {
+            (
+                taken_slice::<false>(content, self.head.bytes),
+                taken_slice::<true>(content, self.tail.bytes),
+            )
         }
+    }
 
-        add_ellipsis!(consumed_slice!()).into()
-    } else {
-        consumed_slice!().into()
+    /// Returns the byte range of `content` that was dropped.
+    #[inline]
+    fn dropped(&self, content: &str) -> Range<usize> {
+        self.head.bytes..content.len() - self.tail.bytes
+    }
+}
+
+/// Information about a truncation, returned by functions like [`truncate_str_with_info`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TruncationInfo<'a, W = usize> {
+    /// The truncated string.
+    pub output: Cow<'a, str>,
+    /// The byte range of the original string kept at the start of the output. This is empty if the
+    /// start of the string was cut off.
+    pub head: Range<usize>,
+    /// The byte range of the original string kept at the end of the output, after the ellipsis.
+    /// This is empty if the end of the string was cut off.
+    pub tail: Range<usize>,
+    /// The width of the output.
+    pub width: W,
+    /// The number of graphemes of the original string that were dropped.
+    pub graphemes_dropped: usize,
+    /// Whether anything was cut from the original string.
+    pub was_truncated: bool,
+}
+
+impl<'a, W: WidthUnit> TruncationInfo<'a, W> {
+    /// Builds the [`TruncationInfo`] for `content` being truncated to `width`. If `cut` is
+    /// [`None`], then `content` fits and is kept as is.
+    fn new<M: Measure<Width = W> + ?Sized>(
+        content: &'a str,
+        cut: Option<Cut<W>>,
+        ellipsis: Ellipsis<'_, W>,
+        width: W,
+        measure: &M,
+    ) -> Self {
+        let output = match cut {
+            Some(cut) => cut.join(content, ellipsis, width, measure).into(),
+            None => content.into(),
+        };
+
+        Self::with_output(content, cut, output, ellipsis, width, measure)
+    }
+
+    /// Like [`TruncationInfo::new`], but with an `output` that has already been built.
+    fn with_output<M: Measure<Width = W> + ?Sized>(
+        content: &'a str,
+        cut: Option<Cut<W>>,
+        output: Cow<'a, str>,
+        ellipsis: Ellipsis<'_, W>,
+        width: W,
+        measure: &M,
+    ) -> Self {
+        match cut {
+            None => Self {
+                output,
+                head: 0..content.len(),
+                tail: content.len()..content.len(),
+                width: measure.str_width(content),
+                graphemes_dropped: 0,
+                was_truncated: false,
+            },
+            Some(cut) => {
+                let ellipsis_width = if ellipsis.width <= width {
+                    ellipsis.width
+                } else {
+                    measure.str_width(ellipsis.fit(width, measure))
+                };
+
+                Self {
+                    output,
+                    head: 0..cut.head.bytes,
+                    tail: content.len() - cut.tail.bytes..content.len(),
+                    width: cut.head.width + ellipsis_width + cut.tail.width,
+                    graphemes_dropped: UnicodeSegmentation::graphemes(
+                        &content[cut.dropped(content)],
+                        true,
+                    )
+                    .count(),
+                    was_truncated: true,
+                }
+            }
+        }
     }
 }
 
 /// Truncates a string to the specified width with a trailing ellipsis character.
 #[inline]
 pub fn truncate_str(content: &str, width: usize) -> Cow<'_, str> {
-    truncate_str_inner::<false>(content, width)
+    truncate_str_inner::<false>(content, width, Ellipsis::DEFAULT, &WidthOptions::DEFAULT)
 }
 
 /// Truncates a string to the specified width with a leading ellipsis character.
 #[inline]
 pub fn truncate_str_leading(content: &str, width: usize) -> Cow<'_, str> {
-    truncate_str_inner::<true>(content, width)
+    truncate_str_inner::<true>(content, width, Ellipsis::DEFAULT, &WidthOptions::DEFAULT)
 }
 
-/// A const-generic function to actually handle the
+/// Truncates a string to the specified width with a trailing ellipsis character like
+/// [`truncate_str`], measured with the given [`WidthOptions`].
 #[inline]
-fn truncate_str_inner<const REVERSE: bool>(content: &str, width: usize) -> Cow<'_, str> {
-    if content.len() <= width {
-        // If the entire string fits in the width, then we just
-        // need to copy the entire string over.
-
-        content.into()
-    } else if let Some(nz_width) = NonZeroUsize::new(width) {
-        // What we are essentially doing is optimizing for the case that
-        // most, if not all of the string is ASCII. As such:
-        // - Step through each byte until (width - 1) is hit or we find a non-ASCII
-        //   byte.
-        // - If the byte is ASCII, then add it.
-        //
-        // If we didn't get a complete truncated string, then continue on treating the rest as graphemes.
-
-        match greedy_ascii_add::<REVERSE>(content, nz_width) {
-            AsciiIterationResult::Complete(text) => text.into(),
-            AsciiIterationResult::Remaining(bytes_consumed) => {
-                handle_remaining::<REVERSE>(content, bytes_consumed, width)
-            }
+pub fn truncate_str_with<'a>(
+    content: &'a str,
+    width: usize,
+    options: &WidthOptions,
+) -> Cow<'a, str> {
+    truncate_str_inner::<false>(
+        content,
+        width,
+        Ellipsis::new(Ellipsis::DEFAULT.text, options),
+        options,
+    )
+}
+
+/// Truncates a string to the specified width with a leading ellipsis character like
+/// [`truncate_str_leading`], measured with the given [`WidthOptions`].
+#[inline]
+pub fn truncate_str_leading_with<'a>(
+    content: &'a str,
+    width: usize,
+    options: &WidthOptions,
+) -> Cow<'a, str> {
+    truncate_str_inner::<true>(
+        content,
+        width,
+        Ellipsis::new(Ellipsis::DEFAULT.text, options),
+        options,
+    )
+}
+
+/// Truncates a string to the specified width with a trailing `ellipsis`, such as `"..."` or `"~"`.
+///
+/// The width of `ellipsis` is measured with [`str_width`], and exactly that much width is reserved
+/// for it. If `ellipsis` is wider than `width` itself, it is truncated to fit.
+#[inline]
+pub fn truncate_str_with_ellipsis<'a>(
+    content: &'a str,
+    width: usize,
+    ellipsis: &str,
+) -> Cow<'a, str> {
+    let options = &WidthOptions::DEFAULT;
+    truncate_str_inner::<false>(content, width, Ellipsis::new(ellipsis, options), options)
+}
+
+/// Truncates a string to the specified width with a leading `ellipsis`, such as `"..."` or `"~"`.
+///
+/// The width of `ellipsis` is measured with [`str_width`], and exactly that much width is reserved
+/// for it. If `ellipsis` is wider than `width` itself, it is truncated to fit.
+#[inline]
+pub fn truncate_str_leading_with_ellipsis<'a>(
+    content: &'a str,
+    width: usize,
+    ellipsis: &str,
+) -> Cow<'a, str> {
+    let options = &WidthOptions::DEFAULT;
+    truncate_str_inner::<true>(content, width, Ellipsis::new(ellipsis, options), options)
+}
+
+/// Truncates a file name to the specified width, keeping its extension intact. For example,
+/// `report-final-version-2024.pdf` becomes `report-final-versi….pdf` rather than
+/// `report-final-version-2024…`.
+///
+/// Multi-part extensions like `.tar.gz` are also kept. If the extension leaves no room for the rest of
+/// the file name, this falls back to [`truncate_str`].
+#[inline]
+pub fn truncate_file_name(name: &str, width: usize) -> Cow<'_, str> {
+    truncate_file_name_with(name, width, &WidthOptions::DEFAULT)
+}
+
+/// Truncates a file name to the specified width like [`truncate_file_name`], measured with the
+/// given [`WidthOptions`].
+pub fn truncate_file_name_with<'a>(
+    name: &'a str,
+    width: usize,
+    options: &WidthOptions,
+) -> Cow<'a, str> {
+    let ellipsis = Ellipsis::new(Ellipsis::DEFAULT.text, options);
+
+    let Some(extension_start) = extension_start(name) else {
+        return truncate_str_inner::<false>(name, width, ellipsis, options);
+    };
+
+    if fits(name, width, options) {
+        return name.into();
+    }
+
+    let (stem, extension) = name.split_at(extension_start);
+    let extension_width = str_width_with(extension, options) + ellipsis.width;
+    if extension_width >= width {
+        return truncate_str_inner::<false>(name, width, ellipsis, options);
+    }
+
+    let kept = take::<false, _>(stem, width - extension_width, options);
+    if kept.bytes == 0 {
+        return truncate_str_inner::<false>(name, width, ellipsis, options);
+    }
+
+    // SAFETY: `kept.bytes` is at a grapheme boundary within `stem`.
+    let stem = unsafe { taken_slice::<false>(stem, kept.bytes) };
+
+    let mut ret = String::with_capacity(stem.len() + ellipsis.text.len() + extension.len());
+    ret.push_str(stem);
+    ret.push_str(ellipsis.text);
+    ret.push_str(extension);
+
+    ret.into()
+}
+
+/// Inner extensions that are kept with the extension after them, like the `tar` in `.tar.gz`.
+const INNER_EXTENSIONS: &[&str] = &["tar", "min", "d"];
+
+/// Returns the byte index of the `.` that starts the extension of a file name, if it has one.
+/// Multi-part extensions like `.tar.gz` or `.min.js` are treated as a single extension.
+fn extension_start(name: &str) -> Option<usize> {
+    // All digits is more likely a version number, like `tool-v1.2.3`.
+    fn is_extension(extension: &str) -> bool {
+        extension.chars().all(char::is_alphanumeric)
+            && extension.chars().any(|c| !c.is_ascii_digit())
+    }
+
+    // A leading dot means a hidden file, not an extension.
+    let start = name.rfind('.').filter(|&start| start > 0)?;
+    let extension = &name[start + 1..];
+
+    if !is_extension(extension) {
+        return None;
+    }
+
+    if let Some(inner_start) = name[..start].rfind('.').filter(|&start| start > 0) {
+        let inner = &name[inner_start + 1..start];
+
+        if INNER_EXTENSIONS
+            .iter()
+            .any(|known| inner.eq_ignore_ascii_case(known))
+        {
+            return Some(inner_start);
         }
+    }
+
+    Some(start)
+}
+
+/// Truncates a string to the specified width, replacing the middle of the string with an ellipsis
+/// character. The start and end of the string are given as close to an equal share of the width as
+/// possible.
+///
+/// This is useful for things like identifiers or paths, where both the start and the end are
+/// important.
+#[inline]
+pub fn truncate_str_middle(content: &str, width: usize) -> Cow<'_, str> {
+    truncate_str_middle_with_bias(content, width, 0.5)
+}
+
+/// Truncates a string to the specified width, replacing the middle of the string with an ellipsis
+/// character.
+///
+/// `bias` is the fraction of the available width given to the start of the string, from `0.0`
+/// (keep only the end) to `1.0` (keep only the start). Any width that one side cannot use, such as
+/// when a wide character does not fit, is given to the other side.
+#[inline]
+pub fn truncate_str_middle_with_bias(content: &str, width: usize, bias: f32) -> Cow<'_, str> {
+    truncate_str_middle_inner(content, width, bias, Ellipsis::DEFAULT)
+}
+
+/// Like [`truncate_str`], but returns a [`TruncationInfo`] describing what was kept.
+#[inline]
+pub fn truncate_str_with_info(content: &str, width: usize) -> TruncationInfo<'_> {
+    let options = &WidthOptions::DEFAULT;
+    let cut = cut_sided::<false, _>(content, width, Ellipsis::DEFAULT, options);
+    TruncationInfo::new(content, cut, Ellipsis::DEFAULT, width, options)
+}
+
+/// Like [`truncate_str_leading`], but returns a [`TruncationInfo`] describing what was kept.
+#[inline]
+pub fn truncate_str_leading_with_info(content: &str, width: usize) -> TruncationInfo<'_> {
+    let options = &WidthOptions::DEFAULT;
+    let cut = cut_sided::<true, _>(content, width, Ellipsis::DEFAULT, options);
+    TruncationInfo::new(content, cut, Ellipsis::DEFAULT, width, options)
+}
+
+/// Like [`truncate_str_middle`], but returns a [`TruncationInfo`] describing what was kept.
+#[inline]
+pub fn truncate_str_middle_with_info(content: &str, width: usize) -> TruncationInfo<'_> {
+    let options = &WidthOptions::DEFAULT;
+    let cut = cut_middle(content, width, 0.5, Ellipsis::DEFAULT, options);
+    TruncationInfo::new(content, cut, Ellipsis::DEFAULT, width, options)
+}
+
+/// Works out what to keep when truncating from the middle, or [`None`] if `content` fits.
+fn cut_middle<M: Measure + ?Sized>(
+    content: &str,
+    width: M::Width,
+    bias: f32,
+    ellipsis: Ellipsis<'_, M::Width>,
+    measure: &M,
+) -> Option<Cut<M::Width>> {
+    if fits(content, width, measure) {
+        return None;
+    } else if ellipsis.width > width {
+        return Some(Cut::default());
+    }
+
+    let available = width - ellipsis.width;
+    let head_width = available.scale(bias.clamp(0.0, 1.0));
+    let head_width = if head_width <= available {
+        head_width
+    } else {
+        available
+    };
+
+    let head = take::<false, M>(content, head_width, measure);
+
+    // SAFETY: `head.bytes` is at a grapheme boundary within `content`.
+    let after_head = unsafe { untaken_slice::<false>(content, head.bytes) };
+    let tail = take::<true, M>(after_head, available - head.width, measure);
+
+    // Give anything the tail could not use back to the head.
+    // SAFETY: `tail.bytes` is at a grapheme boundary within `content`.
+    let before_tail = unsafe { untaken_slice::<true>(content, tail.bytes) };
+    let head = handle_remaining::<false, M>(
+        before_tail,
+        M::Width::default(),
+        head,
+        available - tail.width,
+        measure,
+    );
+
+    Some(Cut { head, tail })
+}
+
+/// Works out what to keep when truncating from either side, or [`None`] if `content` fits.
+#[inline]
+fn cut_sided<const REVERSE: bool, M: Measure + ?Sized>(
+    content: &str,
+    width: M::Width,
+    ellipsis: Ellipsis<'_, M::Width>,
+    measure: &M,
+) -> Option<Cut<M::Width>> {
+    // If the entire string fits in the width, then we just need to copy the entire string over.
+    // Graphemes like tabs can only be measured exactly from the start of the string, so when
+    // taking from the end, check that first.
+    if REVERSE {
+        if fits(content, width, measure) {
+            return None;
+        }
+    } else if measure.is_byte_bounded()
+        && M::Width::from_usize(content.len()) <= width
+        && !content.contains('\t')
+    {
+        return None;
+    }
+
+    let kept = take::<REVERSE, M>(content, width.saturating_sub(ellipsis.width), measure);
+
+    // SAFETY: `kept.bytes` is at a grapheme boundary within `content`.
+    let rest = unsafe { untaken_slice::<REVERSE>(content, kept.bytes) };
+
+    if handle_remaining::<REVERSE, M>(
+        rest,
+        kept.width,
+        Taken::default(),
+        width - kept.width,
+        measure,
+    )
+    .bytes
+        == rest.len()
+    {
+        // Everything fits after all, so no need for an ellipsis.
+        None
+    } else if ellipsis.width > width {
+        Some(Cut::default())
+    } else if REVERSE {
+        Some(Cut {
+            head: Taken::default(),
+            tail: kept,
+        })
     } else {
-        "".into()
+        Some(Cut {
+            head: kept,
+            tail: Taken::default(),
+        })
+    }
+}
+
+/// Handles truncation from the middle.
+#[inline]
+fn truncate_str_middle_inner<'a>(
+    content: &'a str,
+    width: usize,
+    bias: f32,
+    ellipsis: Ellipsis<'_>,
+) -> Cow<'a, str> {
+    let options = &WidthOptions::DEFAULT;
+
+    match cut_middle(content, width, bias, ellipsis, options) {
+        Some(cut) => cut.join(content, ellipsis, width, options).into(),
+        None => content.into(),
+    }
+}
+
+/// A const-generic function to actually handle the truncation from either side.
+#[inline]
+fn truncate_str_inner<'a, const REVERSE: bool>(
+    content: &'a str,
+    width: usize,
+    ellipsis: Ellipsis<'_>,
+    options: &WidthOptions,
+) -> Cow<'a, str> {
+    match cut_sided::<REVERSE, _>(content, width, ellipsis, options) {
+        Some(cut) => cut.join(content, ellipsis, width, options).into(),
+        None => content.into(),
     }
 }
 
@@ -633,10 +1162,10 @@ mod tests {
 
         // This one has a U+FE0F modifier at the end, and is thus considered "emoji-presentation",
         // see https://github.com/fish-shell/fish-shell/issues/10461#issuecomment-2079624670.
+        // Most terminals display these as two columns wide.
         let heart_emoji_pres = "❤️";
         assert_eq!(truncate_str(heart_emoji_pres, 2), heart_emoji_pres);
-        #[cfg(feature = "fish")]
-        assert_eq!(truncate_str(heart_emoji_pres, 1), heart_emoji_pres);
+        assert_eq!(truncate_str(heart_emoji_pres, 1), "…");
         assert_eq!(truncate_str(heart_emoji_pres, 0), "");
 
         let emote = "💎";
@@ -655,6 +1184,47 @@ mod tests {
         assert_eq!(truncate_str(scientist, 0), "");
     }
 
+    #[test]
+    fn truncate_keycaps() {
+        let keycap = "1\u{fe0f}\u{20e3}";
+        let content = format!("a{keycap}b");
+
+        assert_eq!(truncate_str(&content, 4), content);
+        assert_eq!(truncate_str(&content, 3), "a…");
+        assert_eq!(truncate_str(keycap, 1), "…");
+        assert_eq!(truncate_str_leading(&content, 3), "…b");
+        assert_eq!(
+            truncate_str_leading(&format!("xy{keycap}"), 3),
+            format!("…{keycap}")
+        );
+        assert_eq!(
+            truncate_str_middle(&format!("ab{keycap}{keycap}cd"), 5),
+            "ab…cd"
+        );
+    }
+
+    #[test]
+    fn test_truncate_tabs() {
+        assert_eq!(truncate_str("a\tb", 9), "a\tb");
+        assert_eq!(truncate_str("a\tb", 8), "a…");
+        assert_eq!(truncate_str("a\tbcdef", 10), "a\tb…");
+        assert_eq!(
+            truncate_str("a\tb", 3),
+            "a…",
+            "should not fit just because it is short"
+        );
+
+        // Tabs are as wide as a full tab stop when taken from the end.
+        assert_eq!(truncate_str_leading("ab\tc", 9), "ab\tc");
+        assert_eq!(truncate_str_leading("ab\tc", 8), "…c");
+        assert_eq!(truncate_str_leading("abcdefgh\tij", 11), "…\tij");
+
+        assert_eq!(truncate_str_middle("ab\tcdefgh\tij", 12), "ab\tc…ij");
+
+        let info = truncate_str_with_info("a\tbcdef", 10);
+        assert_eq!(info.width, 10);
+    }
+
     #[test]
     fn truncate_emoji_leading() {
         let heart_1 = "♥";
@@ -669,10 +1239,10 @@ mod tests {
 
         // This one has a U+FE0F modifier at the end, and is thus considered "emoji-presentation",
         // see https://github.com/fish-shell/fish-shell/issues/10461#issuecomment-2079624670.
+        // Most terminals display these as two columns wide.
         let heart_emoji_pres = "❤️";
         assert_eq!(truncate_str_leading(heart_emoji_pres, 2), heart_emoji_pres);
-        #[cfg(feature = "fish")]
-        assert_eq!(truncate_str_leading(heart_emoji_pres, 1), heart_emoji_pres);
+        assert_eq!(truncate_str_leading(heart_emoji_pres, 1), "…");
         assert_eq!(truncate_str_leading(heart_emoji_pres, 0), "");
 
         let emote = "💎";
@@ -690,4 +1260,329 @@ mod tests {
         assert_eq!(truncate_str_leading(scientist, 1), "…");
         assert_eq!(truncate_str_leading(scientist, 0), "");
     }
+
+    #[test]
+    fn test_truncate_middle() {
+        let id = "kube-system/coredns-7db6d8ff4d-abcde";
+
+        assert_eq!(
+            truncate_str_middle(id, 40),
+            id,
+            "should match base string as there is extra room"
+        );
+
+        assert_eq!(
+            truncate_str_middle(id, 36),
+            id,
+            "should match base string as there is enough room"
+        );
+
+        assert_eq!(
+            truncate_str_middle(id, 35),
+            "kube-system/cored…-7db6d8ff4d-abcde"
+        );
+        assert_eq!(truncate_str_middle(id, 20), "kube-syste…f4d-abcde");
+        assert_eq!(truncate_str_middle(id, 3), "k…e");
+        assert_eq!(truncate_str_middle(id, 2), "k…");
+        assert_eq!(truncate_str_middle(id, 1), "…");
+        assert_eq!(truncate_str_middle(id, 0), "");
+    }
+
+    #[test]
+    fn test_truncate_middle_cjk() {
+        let cjk = "施氏食獅史";
+
+        assert_eq!(truncate_str_middle(cjk, 10), cjk);
+        assert_eq!(truncate_str_middle(cjk, 9), "施氏…獅史");
+        assert_eq!(truncate_str_middle(cjk, 8), "施氏…史");
+        assert_eq!(truncate_str_middle(cjk, 7), "施…獅史");
+        assert_eq!(truncate_str_middle(cjk, 6), "施…史");
+        assert_eq!(truncate_str_middle(cjk, 2), "…");
+        assert_eq!(truncate_str_middle(cjk, 1), "…");
+        assert_eq!(truncate_str_middle(cjk, 0), "");
+
+        let test = "Test (施氏食獅史) Test";
+        assert_eq!(truncate_str_middle(test, 22), test);
+        assert_eq!(truncate_str_middle(test, 21), "Test (施氏…獅史) Test");
+        assert_eq!(truncate_str_middle(test, 10), "Test …Test");
+
+        let flags = "🇨🇦🇨🇦🇨🇦";
+        assert_eq!(truncate_str_middle(flags, 6), flags);
+        assert_eq!(truncate_str_middle(flags, 5), "🇨🇦…🇨🇦");
+        assert_eq!(truncate_str_middle(flags, 4), "🇨🇦…");
+    }
+
+    #[test]
+    fn test_truncate_middle_unused_width() {
+        assert_eq!(
+            truncate_str_middle("abcdefg施h", 5),
+            "abc…h",
+            "the head should take the width the tail could not use"
+        );
+        assert_eq!(
+            truncate_str_middle("a施bcdefgh", 5),
+            "a…fgh",
+            "the tail should take the width the head could not use"
+        );
+    }
+
+    #[test]
+    fn test_truncate_middle_with_bias() {
+        let content = "0123456789";
+
+        assert_eq!(truncate_str_middle_with_bias(content, 10, 0.0), content);
+        assert_eq!(truncate_str_middle_with_bias(content, 6, 0.0), "…56789");
+        assert_eq!(truncate_str_middle_with_bias(content, 6, 0.25), "0…6789");
+        assert_eq!(truncate_str_middle_with_bias(content, 6, 0.5), "012…89");
+        assert_eq!(truncate_str_middle_with_bias(content, 6, 0.75), "0123…9");
+        assert_eq!(truncate_str_middle_with_bias(content, 6, 1.0), "01234…");
+        assert_eq!(
+            truncate_str_middle_with_bias(content, 6, 2.0),
+            "01234…",
+            "out-of-range biases should be clamped"
+        );
+    }
+
+    #[test]
+    fn test_truncate_custom_ellipsis() {
+        let content = "0123456789";
+
+        assert_eq!(truncate_str_with_ellipsis(content, 10, "..."), content);
+        assert_eq!(truncate_str_with_ellipsis(content, 9, "..."), "012345...");
+        assert_eq!(truncate_str_with_ellipsis(content, 4, "..."), "0...");
+        assert_eq!(truncate_str_with_ellipsis(content, 3, "..."), "...");
+        assert_eq!(
+            truncate_str_with_ellipsis(content, 2, "..."),
+            "..",
+            "the ellipsis should be cut down if it does not fit"
+        );
+        assert_eq!(truncate_str_with_ellipsis(content, 0, "..."), "");
+
+        assert_eq!(truncate_str_with_ellipsis(content, 5, "~"), "0123~");
+        assert_eq!(truncate_str_with_ellipsis(content, 5, ">"), "0123>");
+        assert_eq!(truncate_str_with_ellipsis(content, 5, "[…]"), "01[…]");
+        assert_eq!(
+            truncate_str_with_ellipsis(content, 5, ""),
+            "01234",
+            "an empty ellipsis should just cut the string"
+        );
+
+        let cjk = "施氏食獅史";
+        assert_eq!(truncate_str_with_ellipsis(cjk, 10, "..."), cjk);
+        assert_eq!(truncate_str_with_ellipsis(cjk, 9, "..."), "施氏食...");
+        assert_eq!(truncate_str_with_ellipsis(cjk, 8, "..."), "施氏...");
+        assert_eq!(truncate_str_with_ellipsis(cjk, 4, "..."), "...");
+    }
+
+    #[test]
+    fn test_truncate_custom_ellipsis_leading() {
+        let content = "0123456789";
+
+        assert_eq!(
+            truncate_str_leading_with_ellipsis(content, 10, "..."),
+            content
+        );
+        assert_eq!(
+            truncate_str_leading_with_ellipsis(content, 9, "..."),
+            "...456789"
+        );
+        assert_eq!(
+            truncate_str_leading_with_ellipsis(content, 4, "..."),
+            "...9"
+        );
+        assert_eq!(truncate_str_leading_with_ellipsis(content, 2, "..."), "..");
+        assert_eq!(truncate_str_leading_with_ellipsis(content, 5, "<"), "<6789");
+
+        let cjk = "施氏食獅史";
+        assert_eq!(
+            truncate_str_leading_with_ellipsis(cjk, 9, "..."),
+            "...食獅史"
+        );
+        assert_eq!(truncate_str_leading_with_ellipsis(cjk, 8, "..."), "...獅史");
+    }
+
+    #[test]
+    fn test_truncate_wide_ellipsis() {
+        // U+FE19 is a wide vertical ellipsis.
+        let content = "0123456789";
+        assert_eq!(truncate_str_with_ellipsis(content, 5, "︙"), "012︙");
+        assert_eq!(
+            truncate_str_leading_with_ellipsis(content, 5, "︙"),
+            "︙789"
+        );
+        assert_eq!(truncate_str_with_ellipsis(content, 2, "︙"), "︙");
+        assert_eq!(
+            truncate_str_with_ellipsis(content, 1, "︙"),
+            "",
+            "a wide ellipsis cannot fit in a single column"
+        );
+
+        let cjk = "施氏食獅史";
+        assert_eq!(truncate_str_with_ellipsis(cjk, 9, "︙"), "施氏食︙");
+        assert_eq!(truncate_str_with_ellipsis(cjk, 8, "︙"), "施氏食︙");
+        assert_eq!(truncate_str_leading_with_ellipsis(cjk, 7, "︙"), "︙獅史");
+    }
+
+    #[test]
+    fn test_truncate_str_with() {
+        // Circled digits and the ellipsis are ambiguous, so they are wide in CJK terminals.
+        let content = "①②③④⑤";
+        let options = WidthOptions::new().ambiguous(CellWidth::Wide);
+
+        assert_eq!(truncate_str(content, 5), content);
+        assert_eq!(truncate_str_with(content, 10, &options), content);
+        assert_eq!(truncate_str_with(content, 9, &options), "①②③…");
+        assert_eq!(truncate_str_with(content, 5, &options), "①…");
+        assert_eq!(truncate_str_leading_with(content, 5, &options), "…⑤");
+        assert_eq!(truncate_str_with(content, 2, &options), "…");
+        assert_eq!(truncate_str_with(content, 1, &options), "");
+        assert_eq!(truncate_str_leading_with(content, 1, &options), "");
+    }
+
+    #[test]
+    fn test_truncation_info() {
+        let info = truncate_str_with_info("0123456789", 6);
+        assert_eq!(info.output, "01234…");
+        assert_eq!(info.head, 0..5);
+        assert_eq!(info.tail, 10..10);
+        assert_eq!(info.width, 6);
+        assert_eq!(info.graphemes_dropped, 5);
+        assert!(info.was_truncated);
+
+        let info = truncate_str_with_info("施氏食獅史", 20);
+        assert!(matches!(info.output, Cow::Borrowed("施氏食獅史")));
+        assert_eq!(info.head, 0..15);
+        assert_eq!(info.tail, 15..15);
+        assert_eq!(info.width, 10);
+        assert_eq!(info.graphemes_dropped, 0);
+        assert!(!info.was_truncated);
+
+        let info = truncate_str_with_info("🇨🇦🇨🇦", 3);
+        assert_eq!(info.output, "🇨🇦…");
+        assert_eq!(info.head, 0..8);
+        assert_eq!(info.width, 3);
+        assert_eq!(info.graphemes_dropped, 1);
+
+        let info = truncate_str_with_info("abc", 0);
+        assert_eq!(info.output, "");
+        assert_eq!(info.head, 0..0);
+        assert_eq!(info.tail, 3..3);
+        assert_eq!(info.width, 0);
+        assert_eq!(info.graphemes_dropped, 3);
+        assert!(info.was_truncated);
+    }
+
+    #[test]
+    fn test_truncation_info_leading() {
+        let info = truncate_str_leading_with_info("施氏食獅史", 5);
+        assert_eq!(info.output, "…獅史");
+        assert_eq!(info.head, 0..0);
+        assert_eq!(info.tail, 9..15);
+        assert_eq!(info.width, 5);
+        assert_eq!(info.graphemes_dropped, 3);
+        assert!(info.was_truncated);
+    }
+
+    #[test]
+    fn test_truncation_info_middle() {
+        let id = "kube-system/coredns-7db6d8ff4d-abcde";
+        let info = truncate_str_middle_with_info(id, 20);
+        assert_eq!(info.output, "kube-syste…f4d-abcde");
+        assert_eq!(info.head, 0..10);
+        assert_eq!(info.tail, 27..36);
+        assert_eq!(&id[info.head.clone()], "kube-syste");
+        assert_eq!(&id[info.tail.clone()], "f4d-abcde");
+        assert_eq!(info.width, 20);
+        assert_eq!(info.graphemes_dropped, 17);
+        assert!(info.was_truncated);
+    }
+
+    #[test]
+    fn test_truncate_file_name() {
+        let name = "report-final-version-2024.pdf";
+
+        assert_eq!(truncate_file_name(name, 30), name);
+        assert_eq!(truncate_file_name(name, 29), name);
+        assert_eq!(truncate_file_name(name, 28), "report-final-version-20….pdf");
+        assert_eq!(truncate_file_name(name, 23), "report-final-versi….pdf");
+        assert_eq!(truncate_file_name(name, 6), "r….pdf");
+        assert_eq!(
+            truncate_file_name(name, 5),
+            "repo…",
+            "should fall back to truncate_str if only the extension fits"
+        );
+        assert_eq!(truncate_file_name(name, 0), "");
+
+        assert_eq!(
+            truncate_file_name("backup-2024-01-01.tar.gz", 16),
+            "backup-2….tar.gz"
+        );
+        assert_eq!(
+            truncate_file_name("bundle-with-a-long-name.min.js", 15),
+            "bundle-….min.js"
+        );
+        assert_eq!(
+            truncate_file_name("photo.from.holiday.jpeg", 15),
+            "photo.fro….jpeg"
+        );
+        assert_eq!(
+            truncate_file_name("john.doe.pdf", 11),
+            "john.d….pdf",
+            "only known inner extensions should be kept"
+        );
+        assert_eq!(
+            truncate_file_name("\u{200b}abcdef.txt", 4),
+            "\u{200b}abc…",
+            "should fall back when the extension leaves no room, even for zero width graphemes"
+        );
+        assert_eq!(
+            truncate_file_name("tool-v1.2.3", 8),
+            "tool-v1…",
+            "version numbers aren't extensions"
+        );
+        assert_eq!(truncate_file_name("song-title.mp3", 9), "song….mp3");
+    }
+
+    #[test]
+    fn test_truncate_file_name_with() {
+        // Circled digits are ambiguous, so they are wide in CJK terminals.
+        let name = "①②③④⑤.txt";
+        let options = WidthOptions::new().ambiguous(CellWidth::Wide);
+
+        assert_eq!(truncate_file_name(name, 10), name);
+        assert_eq!(truncate_file_name_with(name, 10, &options), "①②….txt");
+        assert_eq!(truncate_file_name_with(name, 14, &options), name);
+    }
+
+    #[test]
+    fn test_truncate_file_name_no_extension() {
+        assert_eq!(truncate_file_name("README-but-very-long", 8), "README-…");
+        assert_eq!(
+            truncate_file_name(".bashrc-but-very-long", 8),
+            ".bashrc…",
+            "hidden files should not be treated as an extension"
+        );
+        assert_eq!(
+            truncate_file_name("Mr. Smith's notes", 8),
+            "Mr. Smi…",
+            "only alphanumeric extensions should count"
+        );
+        assert_eq!(
+            truncate_file_name("name-that-is-long.extension", 10),
+            "name-that…"
+        );
+    }
+
+    #[test]
+    fn test_truncate_file_name_cjk() {
+        let name = "報告最終版本.pdf";
+
+        assert_eq!(truncate_file_name(name, 16), name);
+        assert_eq!(truncate_file_name(name, 14), "報告最終….pdf");
+        assert_eq!(truncate_file_name(name, 13), "報告最終….pdf");
+        assert_eq!(truncate_file_name(name, 12), "報告最….pdf");
+        assert_eq!(truncate_file_name("報告.pdf", 6), "報告.…");
+
+        assert_eq!(truncate_file_name("💎💎💎💎.png", 9), "💎💎….png");
+    }
 }
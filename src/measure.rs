@@ -0,0 +1,239 @@
+//! Measuring widths in units other than terminal columns.
+
+use std::{
+    fmt::Debug,
+    ops::{Add, Sub},
+};
+
+use unicode_segmentation::UnicodeSegmentation;
+
+/// A unit that widths can be measured in, such as columns or pixels.
+pub trait WidthUnit:
+    Copy + Debug + Default + PartialOrd + Add<Output = Self> + Sub<Output = Self>
+{
+    /// Converts a whole number of units.
+    fn from_usize(n: usize) -> Self;
+
+    /// Converts to a whole number of units, rounding down.
+    fn to_usize(self) -> usize;
+
+    /// Multiplies by `factor`, which is between `0.0` and `1.0`.
+    fn scale(self, factor: f32) -> Self;
+
+    /// Subtracts `other`, stopping at zero.
+    #[inline]
+    fn saturating_sub(self, other: Self) -> Self {
+        if self > other {
+            self - other
+        } else {
+            Self::default()
+        }
+    }
+}
+
+macro_rules! impl_integer_unit {
+    ($($t:ty),*) => {
+        $(
+            impl WidthUnit for $t {
+                #[inline]
+                fn from_usize(n: usize) -> Self {
+                    n.try_into().unwrap_or(<$t>::MAX)
+                }
+
+                #[inline]
+                fn to_usize(self) -> usize {
+                    self.try_into().unwrap_or(usize::MAX)
+                }
+
+                #[inline]
+                fn scale(self, factor: f32) -> Self {
+                    ((self as f32) * factor).round() as $t
+                }
+            }
+        )*
+    };
+}
+
+macro_rules! impl_float_unit {
+    ($($t:ty),*) => {
+        $(
+            impl WidthUnit for $t {
+                #[inline]
+                fn from_usize(n: usize) -> Self {
+                    n as $t
+                }
+
+                #[inline]
+                fn to_usize(self) -> usize {
+                    self as usize
+                }
+
+                #[inline]
+                fn scale(self, factor: f32) -> Self {
+                    self * factor as $t
+                }
+            }
+        )*
+    };
+}
+
+impl_integer_unit!(usize, u32);
+impl_float_unit!(f32, f64);
+
+/// Measures the width of graphemes, for use with
+/// [`Truncator::with_measure`](crate::Truncator::with_measure).
+///
+/// [`WidthOptions`](crate::WidthOptions) measures in terminal columns. Implementing this allows
+/// truncating to other units instead, such as pixels for text drawn with a proportional font.
+///
+/// ```
+/// use unicode_ellipsis::{Measure, Truncator};
+///
+/// /// A font where narrow letters take up less space.
+/// struct Font;
+///
+/// impl Measure for Font {
+///     type Width = f32;
+///
+///     fn grapheme_width(&self, grapheme: &str) -> f32 {
+///         match grapheme {
+///             "i" | "l" | "." => 3.0,
+///             "…" => 9.0,
+///             _ => 7.5,
+///         }
+///     }
+/// }
+///
+/// let truncator = Truncator::with_measure(30.0, Font);
+/// assert_eq!(truncator.truncate("still going"), "stil…");
+/// assert_eq!(truncator.truncate("lil.lil"), "lil.lil");
+/// ```
+pub trait Measure {
+    /// The unit widths are measured in.
+    type Width: WidthUnit;
+
+    /// Returns the width of a single grapheme `grapheme`.
+    fn grapheme_width(&self, grapheme: &str) -> Self::Width;
+
+    /// Returns the width of a single grapheme `grapheme` that starts `column` units into a line.
+    /// This matters for graphemes like tabs, which extend to the next tab stop. By default, this
+    /// ignores `column`.
+    ///
+    /// Where a grapheme starts isn't always known, in which case
+    /// [`grapheme_width`](Self::grapheme_width) is used instead. It should therefore be at least as
+    /// wide as this is for any `column`.
+    #[inline]
+    fn grapheme_width_at(&self, grapheme: &str, column: Self::Width) -> Self::Width {
+        let _ = column;
+        self.grapheme_width(grapheme)
+    }
+
+    /// Returns the width of a str `s`, starting at the start of a line. By default, this adds up
+    /// the widths of its graphemes.
+    #[inline]
+    fn str_width(&self, s: &str) -> Self::Width {
+        s.graphemes(true).fold(Self::Width::default(), |width, g| {
+            width + self.grapheme_width_at(g, width)
+        })
+    }
+
+    /// Returns whether every printable ASCII char is exactly one unit wide, and no grapheme other
+    /// than a tab is wider than its length in bytes. This allows for shortcuts when the text is
+    /// mostly ASCII, and is `false` by default.
+    #[inline]
+    fn is_byte_bounded(&self) -> bool {
+        false
+    }
+}
+
+impl<M: Measure + ?Sized> Measure for &M {
+    type Width = M::Width;
+
+    #[inline]
+    fn grapheme_width(&self, grapheme: &str) -> Self::Width {
+        (**self).grapheme_width(grapheme)
+    }
+
+    #[inline]
+    fn grapheme_width_at(&self, grapheme: &str, column: Self::Width) -> Self::Width {
+        (**self).grapheme_width_at(grapheme, column)
+    }
+
+    #[inline]
+    fn str_width(&self, s: &str) -> Self::Width {
+        (**self).str_width(s)
+    }
+
+    #[inline]
+    fn is_byte_bounded(&self) -> bool {
+        (**self).is_byte_bounded()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Boundary, Side, Truncator};
+
+    /// Measures every grapheme as `.0` units wide.
+    #[derive(Clone)]
+    struct Fixed<W>(W);
+
+    impl<W: WidthUnit> Measure for Fixed<W> {
+        type Width = W;
+
+        fn grapheme_width(&self, _: &str) -> W {
+            self.0
+        }
+    }
+
+    /// Measures like a terminal, but doubles the width of `#`.
+    #[derive(Clone)]
+    struct WideHash;
+
+    impl Measure for WideHash {
+        type Width = u32;
+
+        fn grapheme_width(&self, grapheme: &str) -> u32 {
+            match grapheme {
+                "#" => 2,
+                _ => crate::grapheme_width(grapheme) as u32,
+            }
+        }
+    }
+
+    #[test]
+    fn test_units() {
+        let content = "abcdefghij";
+
+        let truncator = Truncator::with_measure(25.0f32, Fixed(5.0f32));
+        assert_eq!(truncator.truncate(content), "abcd…");
+        assert_eq!(
+            truncator.clone().side(Side::Leading).truncate(content),
+            "…ghij"
+        );
+        assert_eq!(
+            truncator.clone().side(Side::Middle).truncate(content),
+            "ab…ij"
+        );
+        assert_eq!(truncator.truncate("abcde"), "abcde");
+
+        let info = truncator.truncate_with_info(content);
+        assert_eq!(info.width, 25.0);
+        assert_eq!(info.graphemes_dropped, 6);
+
+        let truncator = Truncator::with_measure(2.5f64, Fixed(0.5f64)).ellipsis("...");
+        assert_eq!(truncator.truncate(content), "ab...");
+
+        let truncator = Truncator::with_measure(6u32, Fixed(1u32)).boundary(Boundary::Word);
+        assert_eq!(truncator.truncate("ab cd ef"), "ab cd…");
+    }
+
+    #[test]
+    fn test_not_byte_bounded() {
+        let truncator = Truncator::with_measure(4, WideHash);
+        assert_eq!(truncator.truncate("####"), "#…");
+        assert_eq!(truncator.truncate("a#b"), "a#b");
+        assert_eq!(truncator.clone().side(Side::Leading).truncate("ab##"), "…#");
+    }
+}
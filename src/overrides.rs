@@ -0,0 +1,431 @@
+//! User-supplied widths that take precedence over the built-in width tables.
+
+use std::{collections::HashMap, fmt, ops::RangeInclusive, str::FromStr};
+
+/// A table of widths to use instead of the built-in ones, for use with
+/// [`WidthOptions::overrides`](crate::WidthOptions::overrides).
+///
+/// Widths can be set for chars, ranges of chars or whole graphemes. A grapheme containing chars
+/// with overridden widths is measured as the sum of the widths of its chars, unless the grapheme
+/// itself has been overridden.
+///
+/// ```
+/// use unicode_ellipsis::{str_width_with, WidthOptions, WidthOverrides};
+///
+/// let mut overrides = WidthOverrides::new();
+/// overrides.insert_grapheme("🇨🇦", 4);
+/// overrides.insert_range('\u{e0a0}'..='\u{e0a3}', 2);
+///
+/// let options = WidthOptions::new().overrides(overrides);
+/// assert_eq!(str_width_with("🇨🇦!", &options), 5);
+/// assert_eq!(str_width_with("\u{e0a0} main", &options), 7);
+/// ```
+///
+/// Overrides can also be parsed from text, with one override per line. Each line has either a
+/// codepoint, a range of codepoints or a sequence of codepoints forming a grapheme, followed by
+/// its width. Anything after a `#` is ignored.
+///
+/// ```
+/// use unicode_ellipsis::WidthOverrides;
+///
+/// let overrides: WidthOverrides = "
+/// ## Nerd Font icons
+/// U+E0A0..U+E0A3 2
+/// U+F0001        2
+///
+/// U+2764 U+FE0F  1 # ❤️
+/// "
+/// .parse()
+/// .unwrap();
+///
+/// assert_eq!(overrides.char('\u{e0a1}'), Some(2));
+/// assert_eq!(overrides.grapheme("❤️"), Some(1));
+/// ```
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct WidthOverrides {
+    graphemes: HashMap<String, usize>,
+    /// Overridden ranges of chars, sorted and non-overlapping.
+    chars: Vec<(RangeInclusive<char>, usize)>,
+    /// Whether ASCII is still one column wide, and no override is wider than its length in bytes.
+    byte_bounded: bool,
+}
+
+impl Default for WidthOverrides {
+    fn default() -> Self {
+        Self {
+            graphemes: HashMap::new(),
+            chars: Vec::new(),
+            byte_bounded: true,
+        }
+    }
+}
+
+impl WidthOverrides {
+    /// Creates a new, empty set of [`WidthOverrides`].
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the width of a grapheme `grapheme`, returning the width it previously had, if any.
+    pub fn insert_grapheme(&mut self, grapheme: impl Into<String>, width: usize) -> Option<usize> {
+        let grapheme = grapheme.into();
+
+        if width > grapheme.len() || (grapheme.is_ascii() && width != grapheme.len()) {
+            self.byte_bounded = false;
+        }
+
+        self.graphemes.insert(grapheme, width)
+    }
+
+    /// Sets the width of a char `c`.
+    pub fn insert_char(&mut self, c: char, width: usize) {
+        self.insert_range(c..=c, width);
+    }
+
+    /// Sets the width of every char in `range`. This takes precedence over any existing overrides
+    /// for those chars.
+    pub fn insert_range(&mut self, range: RangeInclusive<char>, width: usize) {
+        if range.is_empty() {
+            return;
+        }
+
+        if width > range.start().len_utf8() || (range.start().is_ascii() && width != 1) {
+            self.byte_bounded = false;
+        }
+
+        let (start, end) = range.into_inner();
+
+        // The existing ranges that overlap with the new one, which get replaced.
+        let first = self
+            .chars
+            .partition_point(|(range, _)| *range.end() < start);
+        let last = self
+            .chars
+            .partition_point(|(range, _)| *range.start() <= end);
+
+        // Keep the parts of the first and last overlapping ranges that stick out either side.
+        let mut replacement = Vec::with_capacity(3);
+        if let Some((range, width)) = self.chars[first..last].first() {
+            if *range.start() < start {
+                replacement.push((*range.start()..=prev_char(start), *width));
+            }
+        }
+        replacement.push((start..=end, width));
+        if let Some((range, width)) = self.chars[first..last].last() {
+            if *range.end() > end {
+                replacement.push((next_char(end)..=*range.end(), *width));
+            }
+        }
+
+        self.chars.splice(first..last, replacement);
+    }
+
+    /// Returns the width of a grapheme `grapheme`, if it has been overridden.
+    pub fn grapheme(&self, grapheme: &str) -> Option<usize> {
+        self.graphemes.get(grapheme).copied()
+    }
+
+    /// Returns the width of a char `c`, if it has been overridden.
+    pub fn char(&self, c: char) -> Option<usize> {
+        self.chars
+            .binary_search_by(|(range, _)| {
+                if range.contains(&c) {
+                    std::cmp::Ordering::Equal
+                } else {
+                    range.start().cmp(&c)
+                }
+            })
+            .ok()
+            .map(|index| self.chars[index].1)
+    }
+
+    /// Returns whether there are no overrides.
+    pub fn is_empty(&self) -> bool {
+        self.graphemes.is_empty() && self.chars.is_empty()
+    }
+
+    /// Returns whether there are any char overrides.
+    #[inline]
+    pub(crate) fn has_chars(&self) -> bool {
+        !self.chars.is_empty()
+    }
+
+    /// Returns whether ASCII is still one column wide, and no grapheme is wider than its length in
+    /// bytes.
+    #[inline]
+    pub(crate) fn is_byte_bounded(&self) -> bool {
+        self.byte_bounded
+    }
+}
+
+/// Returns the char before `c`, skipping over surrogates. `c` must not be `'\0'`.
+#[inline]
+fn prev_char(c: char) -> char {
+    char::from_u32(u32::from(c) - 1).unwrap_or('\u{d7ff}')
+}
+
+/// Returns the char after `c`, skipping over surrogates. `c` must not be [`char::MAX`].
+#[inline]
+fn next_char(c: char) -> char {
+    char::from_u32(u32::from(c) + 1).unwrap_or('\u{e000}')
+}
+
+impl FromStr for WidthOverrides {
+    type Err = ParseOverridesError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut overrides = WidthOverrides::new();
+
+        for (index, line) in s.lines().enumerate() {
+            let error = |kind| ParseOverridesError {
+                line: index + 1,
+                kind,
+            };
+
+            let line = line.split('#').next().unwrap_or_default();
+            let mut tokens: Vec<&str> = line.split_whitespace().collect();
+
+            let Some(width) = tokens.pop() else {
+                continue;
+            };
+            if tokens.is_empty() {
+                return Err(error(ParseOverridesErrorKind::MissingWidth));
+            }
+            let width = width
+                .parse()
+                .map_err(|_| error(ParseOverridesErrorKind::InvalidWidth))?;
+
+            if let [token] = tokens[..] {
+                if let Some((start, end)) = token.split_once("..") {
+                    let start = parse_codepoint(start).ok_or(error(
+                        ParseOverridesErrorKind::InvalidCodepoint(start.to_owned()),
+                    ))?;
+                    let end = parse_codepoint(end).ok_or(error(
+                        ParseOverridesErrorKind::InvalidCodepoint(end.to_owned()),
+                    ))?;
+
+                    if start > end {
+                        return Err(error(ParseOverridesErrorKind::InvalidRange));
+                    }
+
+                    overrides.insert_range(start..=end, width);
+                    continue;
+                }
+            }
+
+            let grapheme = tokens
+                .iter()
+                .map(|token| {
+                    parse_codepoint(token).ok_or(error(ParseOverridesErrorKind::InvalidCodepoint(
+                        (*token).to_owned(),
+                    )))
+                })
+                .collect::<Result<String, _>>()?;
+
+            let mut chars = grapheme.chars();
+            match (chars.next(), chars.next()) {
+                (Some(c), None) => overrides.insert_char(c, width),
+                _ => {
+                    overrides.insert_grapheme(grapheme, width);
+                }
+            }
+        }
+
+        Ok(overrides)
+    }
+}
+
+/// Parses a codepoint written like `U+E0A0`.
+fn parse_codepoint(token: &str) -> Option<char> {
+    let hex = token
+        .strip_prefix("U+")
+        .or_else(|| token.strip_prefix("u+"))?;
+
+    if hex.is_empty() || hex.len() > 6 {
+        return None;
+    }
+
+    char::from_u32(u32::from_str_radix(hex, 16).ok()?)
+}
+
+/// An error from parsing [`WidthOverrides`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ParseOverridesError {
+    line: usize,
+    kind: ParseOverridesErrorKind,
+}
+
+impl ParseOverridesError {
+    /// The line the error is on, starting from 1.
+    pub fn line(&self) -> usize {
+        self.line
+    }
+
+    /// What went wrong.
+    pub fn kind(&self) -> &ParseOverridesErrorKind {
+        &self.kind
+    }
+}
+
+impl fmt::Display for ParseOverridesError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "line {}: ", self.line)?;
+
+        match &self.kind {
+            ParseOverridesErrorKind::MissingWidth => f.write_str("missing width"),
+            ParseOverridesErrorKind::InvalidWidth => f.write_str("invalid width"),
+            ParseOverridesErrorKind::InvalidCodepoint(token) => {
+                write!(f, "invalid codepoint `{token}`")
+            }
+            ParseOverridesErrorKind::InvalidRange => {
+                f.write_str("the start of a range is after its end")
+            }
+        }
+    }
+}
+
+impl std::error::Error for ParseOverridesError {}
+
+/// The kind of a [`ParseOverridesError`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum ParseOverridesErrorKind {
+    /// A line only has a codepoint or a width, but not both.
+    MissingWidth,
+    /// The width is not a valid number.
+    InvalidWidth,
+    /// A codepoint is not written like `U+E0A0`, or is not a valid char.
+    InvalidCodepoint(String),
+    /// The start of a range is after its end.
+    InvalidRange,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse() {
+        let overrides: WidthOverrides = "
+            # A comment.
+            U+E0A0..U+E0A3 2
+            u+e0a2 1 # Later lines take precedence.
+            U+1F1E8 U+1F1E6 4
+            U+61 1
+        "
+        .parse()
+        .unwrap();
+
+        assert_eq!(overrides.char('\u{e0a0}'), Some(2));
+        assert_eq!(overrides.char('\u{e0a2}'), Some(1));
+        assert_eq!(overrides.char('\u{e0a3}'), Some(2));
+        assert_eq!(overrides.char('\u{e0a4}'), None);
+        assert_eq!(overrides.grapheme("🇨🇦"), Some(4));
+        assert_eq!(overrides.char('a'), Some(1));
+        assert!(overrides.is_byte_bounded());
+
+        assert!("".parse::<WidthOverrides>().unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_insert_range() {
+        let mut overrides = WidthOverrides::new();
+        overrides.insert_range('b'..='y', 2);
+        overrides.insert_range('f'..='j', 0);
+        overrides.insert_char('h', 1);
+        overrides.insert_range('a'..='d', 3);
+        overrides.insert_range('x'..='z', 1);
+
+        for (c, width) in [
+            ('`', None),
+            ('a', Some(3)),
+            ('d', Some(3)),
+            ('e', Some(2)),
+            ('f', Some(0)),
+            ('g', Some(0)),
+            ('h', Some(1)),
+            ('i', Some(0)),
+            ('j', Some(0)),
+            ('k', Some(2)),
+            ('w', Some(2)),
+            ('x', Some(1)),
+            ('z', Some(1)),
+            ('{', None),
+        ] {
+            assert_eq!(overrides.char(c), width, "{c:?}");
+        }
+
+        // A later range covering everything replaces the earlier ones entirely.
+        overrides.insert_range('\0'..=char::MAX, 1);
+        assert_eq!(overrides.chars, [('\0'..=char::MAX, 1)]);
+
+        // Ranges are split around surrogates.
+        overrides.insert_char('\u{e000}', 2);
+        assert_eq!(overrides.char('\u{d7ff}'), Some(1));
+        assert_eq!(overrides.char('\u{e000}'), Some(2));
+        assert_eq!(overrides.char('\u{e001}'), Some(1));
+        assert_eq!(
+            overrides.chars,
+            [
+                ('\0'..='\u{d7ff}', 1),
+                ('\u{e000}'..='\u{e000}', 2),
+                ('\u{e001}'..=char::MAX, 1)
+            ]
+        );
+
+        overrides.insert_range('z'..='a', 5);
+        assert_eq!(overrides.char('m'), Some(1));
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        for (text, line, kind) in [
+            ("U+E0A0", 1, ParseOverridesErrorKind::MissingWidth),
+            ("\nU+E0A0 wide", 2, ParseOverridesErrorKind::InvalidWidth),
+            (
+                "E0A0 2",
+                1,
+                ParseOverridesErrorKind::InvalidCodepoint("E0A0".to_owned()),
+            ),
+            (
+                "U+D800 2",
+                1,
+                ParseOverridesErrorKind::InvalidCodepoint("U+D800".to_owned()),
+            ),
+            (
+                "U+E0A0..U+ 2",
+                1,
+                ParseOverridesErrorKind::InvalidCodepoint("U+".to_owned()),
+            ),
+            ("U+E0A3..U+E0A0 2", 1, ParseOverridesErrorKind::InvalidRange),
+        ] {
+            let err = text.parse::<WidthOverrides>().unwrap_err();
+            assert_eq!(err.line(), line, "{text}");
+            assert_eq!(err.kind(), &kind, "{text}");
+        }
+
+        assert_eq!(
+            "\n\nU+E0A0 wide"
+                .parse::<WidthOverrides>()
+                .unwrap_err()
+                .to_string(),
+            "line 3: invalid width"
+        );
+    }
+
+    #[test]
+    fn test_byte_bounded() {
+        let mut overrides = WidthOverrides::new();
+        overrides.insert_range('\u{e0a0}'..='\u{e0a3}', 3);
+        overrides.insert_grapheme("❤️", 2);
+        assert!(overrides.is_byte_bounded());
+
+        let mut overrides = WidthOverrides::new();
+        overrides.insert_char('-', 2);
+        assert!(!overrides.is_byte_bounded());
+
+        let mut overrides = WidthOverrides::new();
+        overrides.insert_char('é', 3);
+        assert!(!overrides.is_byte_bounded());
+    }
+}
@@ -0,0 +1,316 @@
+//! Truncation for file paths.
+
+use std::borrow::Cow;
+
+use unicode_segmentation::UnicodeSegmentation;
+
+use crate::{str_width_with, truncate_str_inner, Ellipsis, Measure, WidthOptions};
+
+/// A single component of a path, such as a directory or file name.
+struct Component<'a> {
+    text: &'a str,
+    width: usize,
+    /// The component shortened to its first grapheme, fish-prompt style.
+    short: &'a str,
+    short_width: usize,
+    /// The separator that follows this component, or `""` if this is the last component.
+    separator: &'a str,
+}
+
+impl<'a> Component<'a> {
+    fn new(text: &'a str, separator: &'a str, is_last: bool, options: &WidthOptions) -> Self {
+        let width = str_width_with(text, options);
+
+        // Don't shorten file names, or things like drive letters (`C:`) where a single character
+        // would be ambiguous.
+        let short = if is_last || text.ends_with(':') {
+            text
+        } else {
+            // Keep the leading `.` of hidden directories, so `.config` becomes `.c`.
+            let skip = usize::from(text.starts_with('.'));
+            let first_len = text[skip..]
+                .graphemes(true)
+                .next()
+                .map_or(0, |grapheme| grapheme.len());
+
+            &text[..skip + first_len]
+        };
+
+        Self {
+            text,
+            width,
+            short,
+            short_width: str_width_with(short, options),
+            separator,
+        }
+    }
+
+    #[inline]
+    fn text(&self, shorten: bool) -> &'a str {
+        if shorten {
+            self.short
+        } else {
+            self.text
+        }
+    }
+
+    #[inline]
+    fn width(&self, shorten: bool) -> usize {
+        if shorten {
+            self.short_width
+        } else {
+            self.width
+        }
+    }
+}
+
+/// One way of displaying a path.
+#[derive(Clone, Copy)]
+struct Candidate {
+    /// How many components from the start are shortened.
+    shortened: usize,
+    /// How many components are kept from the start and the end; anything in between is elided.
+    /// [`None`] means nothing is elided.
+    kept: Option<(usize, usize)>,
+}
+
+/// Splits `path` into its components, on both `/` and `\`.
+fn components<'a>(path: &'a str, options: &WidthOptions) -> Vec<Component<'a>> {
+    let mut components = Vec::new();
+    let mut start = 0;
+
+    for (index, separator) in path.match_indices(['/', '\\']) {
+        components.push(Component::new(
+            &path[start..index],
+            separator,
+            false,
+            options,
+        ));
+        start = index + separator.len();
+    }
+    components.push(Component::new(&path[start..], "", true, options));
+
+    components
+}
+
+/// Returns whether component `index` of `count` components is elided in `candidate`.
+#[inline]
+fn is_elided(candidate: Candidate, index: usize, count: usize) -> bool {
+    match candidate.kept {
+        Some((head, tail)) => index >= head && index < count - tail,
+        None => false,
+    }
+}
+
+fn candidate_width(
+    components: &[Component<'_>],
+    candidate: Candidate,
+    ellipsis: Ellipsis<'_>,
+) -> usize {
+    let count = components.len();
+    let mut width = 0;
+
+    for (index, component) in components.iter().enumerate() {
+        if is_elided(candidate, index, count) {
+            // Only count the ellipsis and a single separator once for the entire elided run.
+            if Some(index) == candidate.kept.map(|(head, _)| head) {
+                width += ellipsis.width + 1;
+            }
+        } else {
+            width += component.width(index < candidate.shortened)
+                + usize::from(!component.separator.is_empty());
+        }
+    }
+
+    width
+}
+
+fn render(components: &[Component<'_>], candidate: Candidate, ellipsis: Ellipsis<'_>) -> String {
+    let count = components.len();
+    let mut ret = String::new();
+
+    for (index, component) in components.iter().enumerate() {
+        if is_elided(candidate, index, count) {
+            if !is_elided(candidate, index + 1, count) {
+                ret.push_str(ellipsis.text);
+                ret.push_str(component.separator);
+            }
+        } else {
+            ret.push_str(component.text(index < candidate.shortened));
+            ret.push_str(component.separator);
+        }
+    }
+
+    ret
+}
+
+/// Truncates a file path to the specified width, trying to keep the most useful parts of it.
+/// Both `/` and `\` are treated as separators.
+///
+/// In order, this will try to:
+/// 1. Elide directories from the middle of the path while keeping the first component and the
+///    last two, like `~/projects/…/src/lib.rs`.
+/// 2. Shorten directories to their first grapheme, starting from the left, like `~/p/c/s/lib.rs`.
+/// 3. Elide directories from the middle of the shortened path, down to `~/…/lib.rs`, then `…/lib.rs`.
+/// 4. Truncate the file name itself with [`truncate_str`](crate::truncate_str).
+///
+/// ```
+/// use unicode_ellipsis::truncate_path;
+///
+/// let path = "~/projects/client/src/lib.rs";
+/// assert_eq!(truncate_path(path, 30), path);
+/// assert_eq!(truncate_path(path, 24), "~/projects/…/src/lib.rs");
+/// ```
+#[inline]
+pub fn truncate_path(path: &str, width: usize) -> Cow<'_, str> {
+    truncate_path_with(path, width, &WidthOptions::DEFAULT)
+}
+
+/// Truncates a file path to the specified width like [`truncate_path`], measured with the given
+/// [`WidthOptions`].
+pub fn truncate_path_with<'a>(path: &'a str, width: usize, options: &WidthOptions) -> Cow<'a, str> {
+    if (options.is_byte_bounded() && path.len() <= width && !path.contains('\t'))
+        || str_width_with(path, options) <= width
+    {
+        return path.into();
+    }
+
+    let ellipsis = Ellipsis::new(Ellipsis::DEFAULT.text, options);
+    let components = components(path, options);
+    let count = components.len();
+
+    let elided = (3..count).rev().map(|kept| Candidate {
+        shortened: 0,
+        kept: Some((kept - kept.div_ceil(2), kept.div_ceil(2))),
+    });
+    let shortened = (1..count).map(|shortened| Candidate {
+        shortened,
+        kept: None,
+    });
+    let shortened_elided = (1..count - 1).rev().map(|kept| Candidate {
+        shortened: count,
+        kept: Some((kept - kept.div_ceil(2), kept.div_ceil(2))),
+    });
+
+    let candidate = elided
+        .chain(shortened)
+        .chain(shortened_elided)
+        .find(|candidate| candidate_width(&components, *candidate, ellipsis) <= width);
+
+    match candidate {
+        Some(candidate) => render(&components, candidate, ellipsis).into(),
+        None => {
+            let file_name = &components[count - 1];
+
+            if count > 1 && file_name.width + ellipsis.width < width {
+                let separator = components[count - 2].separator;
+                format!("{}{separator}{}", ellipsis.text, file_name.text).into()
+            } else {
+                truncate_str_inner::<false>(file_name.text, width, ellipsis, options)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::CellWidth;
+
+    #[test]
+    fn test_truncate_path() {
+        let path = "~/projects/client/src/lib.rs";
+
+        assert_eq!(truncate_path(path, 30), path);
+        assert_eq!(truncate_path(path, 28), path);
+        assert_eq!(truncate_path(path, 27), "~/projects/…/src/lib.rs");
+        assert_eq!(truncate_path(path, 23), "~/projects/…/src/lib.rs");
+        assert_eq!(truncate_path(path, 22), "~/…/src/lib.rs");
+        assert_eq!(truncate_path(path, 14), "~/…/src/lib.rs");
+        assert_eq!(truncate_path(path, 13), "~/…/s/lib.rs");
+        assert_eq!(truncate_path(path, 11), "~/…/lib.rs");
+        assert_eq!(truncate_path(path, 9), "…/lib.rs");
+        assert_eq!(truncate_path(path, 6), "lib.rs");
+        assert_eq!(truncate_path(path, 5), "lib.…");
+        assert_eq!(truncate_path(path, 0), "");
+
+        // Tabs are wider than their single byte.
+        assert_eq!(truncate_path("a\tb", 3), "a…");
+    }
+
+    #[test]
+    fn test_truncate_path_with() {
+        // Circled digits are ambiguous, so the directory name is twice as wide in a CJK terminal.
+        let path = "~/①②③④/src/lib.rs";
+        let options = WidthOptions::new().ambiguous(CellWidth::Wide);
+
+        assert_eq!(truncate_path(path, 17), path);
+        assert_eq!(truncate_path_with(path, 17, &options), "~/…/src/lib.rs");
+        assert_eq!(truncate_path_with(path, 21, &options), path);
+    }
+
+    #[test]
+    fn test_truncate_path_shortened() {
+        let path = "~/work/some-very-long-directory-name/main.rs";
+
+        assert_eq!(truncate_path(path, 44), path);
+        assert_eq!(
+            truncate_path(path, 43),
+            "~/…/some-very-long-directory-name/main.rs"
+        );
+        assert_eq!(truncate_path(path, 20), "~/w/s/main.rs");
+        assert_eq!(truncate_path(path, 12), "~/…/main.rs");
+
+        let path = "/home/user/.config/app/settings.toml";
+        assert_eq!(truncate_path(path, 35), "/home/…/.config/app/settings.toml");
+        assert_eq!(truncate_path(path, 19), "/…/a/settings.toml");
+    }
+
+    #[test]
+    fn test_truncate_path_hidden_directories() {
+        let path = "/home/.config/some-application-name/settings.toml";
+
+        assert_eq!(
+            truncate_path(path, 30),
+            "/h/.c/s/settings.toml",
+            "hidden directories should keep their leading dot"
+        );
+    }
+
+    #[test]
+    fn test_truncate_path_windows() {
+        let path = r"C:\Users\someone\Documents\report.docx";
+
+        assert_eq!(truncate_path(path, 38), path);
+        assert_eq!(truncate_path(path, 37), r"C:\Users\…\Documents\report.docx");
+        assert_eq!(truncate_path(path, 31), r"C:\…\Documents\report.docx");
+        assert_eq!(
+            truncate_path(path, 25),
+            r"C:\U\s\D\report.docx",
+            "drive letters should not be shortened"
+        );
+        assert_eq!(truncate_path(path, 15), r"…\report.docx");
+    }
+
+    #[test]
+    fn test_truncate_path_cjk() {
+        let path = "/家/文件夾/項目/檔案.txt";
+
+        assert_eq!(truncate_path(path, 24), path);
+        assert_eq!(truncate_path(path, 23), "/家/…/項目/檔案.txt");
+        assert_eq!(truncate_path(path, 18), "/…/項目/檔案.txt");
+        assert_eq!(truncate_path(path, 15), "/…/項/檔案.txt");
+        assert_eq!(truncate_path(path, 13), "/…/檔案.txt");
+        assert_eq!(truncate_path(path, 10), "…/檔案.txt");
+        assert_eq!(truncate_path(path, 9), "檔案.txt");
+        assert_eq!(truncate_path(path, 6), "檔案.…");
+    }
+
+    #[test]
+    fn test_truncate_path_short() {
+        assert_eq!(truncate_path("file.txt", 8), "file.txt");
+        assert_eq!(truncate_path("file.txt", 6), "file.…");
+        assert_eq!(truncate_path("a/b", 3), "a/b");
+        assert_eq!(truncate_path("a/b", 2), "b");
+    }
+}
@@ -0,0 +1,250 @@
+//! Measuring widths by asking the terminal where its cursor is.
+
+use std::{
+    io::{self, Read, Write},
+    thread,
+    time::{Duration, Instant},
+};
+
+use crate::WidthOverrides;
+
+/// Measures the width the terminal actually displays graphemes with, by printing each one and
+/// then requesting a [cursor position report](https://vt100.net/docs/vt510-rm/DSR-CPR.html).
+///
+/// The terminal should be in raw mode while probing so the report can be read back without
+/// waiting for a newline, and without it being echoed. Probing prints to the current line and
+/// clears it afterwards.
+///
+/// The [timeout](Self::timeout) can only be enforced if reads from the terminal don't block
+/// forever, so the terminal must either be non-blocking (`O_NONBLOCK`) or have a read timeout set
+/// (`VMIN` of 0 and a nonzero `VTIME`). Otherwise, probing hangs if the terminal never answers.
+///
+/// ```no_run
+/// use std::{fs::File, process::Command};
+///
+/// use unicode_ellipsis::{WidthOptions, WidthOverrides, WidthProbe};
+///
+/// // Enable raw mode, with reads that give up after 0.1 seconds.
+/// Command::new("stty")
+///     .args(["raw", "-echo", "min", "0", "time", "1"])
+///     .stdin(File::open("/dev/tty")?)
+///     .status()?;
+///
+/// let mut tty = File::options().read(true).write(true).open("/dev/tty")?;
+///
+/// let mut overrides = WidthOverrides::new();
+/// WidthProbe::new().probe_all(&mut tty, ["❤️", "👨‍👩‍👧", "🇨🇦"], &mut overrides)?;
+///
+/// let options = WidthOptions::new().overrides(overrides);
+/// # Ok::<(), std::io::Error>(())
+/// ```
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct WidthProbe {
+    timeout: Duration,
+}
+
+impl Default for WidthProbe {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl WidthProbe {
+    /// Creates a new [`WidthProbe`], which waits up to 100 ms for each response.
+    pub fn new() -> Self {
+        Self {
+            timeout: Duration::from_millis(100),
+        }
+    }
+
+    /// Sets how long to wait for the terminal to respond to each request.
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// Measures the width of a single grapheme `grapheme`.
+    ///
+    /// `terminal` must be non-blocking or have a read timeout set, as a blocking read can't be
+    /// interrupted once the [timeout](Self::timeout) passes. Reads that return
+    /// [`WouldBlock`](io::ErrorKind::WouldBlock), [`TimedOut`](io::ErrorKind::TimedOut) or no
+    /// bytes are retried until the timeout passes, after which an error of kind
+    /// [`TimedOut`](io::ErrorKind::TimedOut) is returned.
+    pub fn probe<T: Read + Write>(&self, terminal: &mut T, grapheme: &str) -> io::Result<usize> {
+        write!(terminal, "\r{grapheme}\x1b[6n")?;
+        terminal.flush()?;
+
+        let column = self.read_column(terminal);
+
+        write!(terminal, "\r\x1b[2K")?;
+        terminal.flush()?;
+
+        // Columns are 1-based, and the cursor starts at the first one.
+        Ok(column?.saturating_sub(1))
+    }
+
+    /// Measures the width of each grapheme in `graphemes` and stores them in `overrides`,
+    /// stopping at the first error.
+    pub fn probe_all<'a, T: Read + Write>(
+        &self,
+        terminal: &mut T,
+        graphemes: impl IntoIterator<Item = &'a str>,
+        overrides: &mut WidthOverrides,
+    ) -> io::Result<()> {
+        for grapheme in graphemes {
+            let width = self.probe(terminal, grapheme)?;
+            overrides.insert_grapheme(grapheme, width);
+        }
+
+        Ok(())
+    }
+
+    /// Reads a cursor position report of the form `ESC [ row ; column R`, returning the column.
+    /// Anything before the report is skipped.
+    fn read_column<T: Read>(&self, terminal: &mut T) -> io::Result<usize> {
+        let deadline = Instant::now() + self.timeout;
+        let mut report = Vec::new();
+        let mut byte = [0];
+
+        loop {
+            match terminal.read(&mut byte) {
+                Ok(1) => {
+                    if byte[0] == b'\x1b' {
+                        report.clear();
+                    }
+                    report.push(byte[0]);
+
+                    if byte[0] == b'R' {
+                        if let Some(column) = parse_report(&report) {
+                            return Ok(column);
+                        }
+                    }
+                    continue;
+                }
+                Ok(_) => {}
+                Err(err)
+                    if matches!(
+                        err.kind(),
+                        io::ErrorKind::WouldBlock
+                            | io::ErrorKind::TimedOut
+                            | io::ErrorKind::Interrupted
+                    ) => {}
+                Err(err) => return Err(err),
+            }
+
+            if Instant::now() >= deadline {
+                return Err(io::Error::new(
+                    io::ErrorKind::TimedOut,
+                    "the terminal did not report its cursor position",
+                ));
+            }
+
+            thread::sleep(Duration::from_millis(1));
+        }
+    }
+}
+
+/// Parses a cursor position report of the form `ESC [ row ; column R`, returning the column.
+fn parse_report(report: &[u8]) -> Option<usize> {
+    let params = std::str::from_utf8(report)
+        .ok()?
+        .strip_prefix("\x1b[")?
+        .strip_suffix('R')?;
+    let (row, column) = params.split_once(';')?;
+
+    row.parse::<usize>().ok()?;
+    column.parse().ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::{HashMap, VecDeque};
+
+    use super::*;
+
+    /// A scripted terminal that answers cursor position requests.
+    #[derive(Default)]
+    struct FakeTerminal {
+        widths: HashMap<&'static str, usize>,
+        written: String,
+        responses: VecDeque<u8>,
+        silent: bool,
+    }
+
+    impl Read for FakeTerminal {
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+            match self.responses.pop_front() {
+                Some(byte) => {
+                    buf[0] = byte;
+                    Ok(1)
+                }
+                None => Err(io::ErrorKind::WouldBlock.into()),
+            }
+        }
+    }
+
+    impl Write for FakeTerminal {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            self.written.push_str(std::str::from_utf8(buf).unwrap());
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            if let Some(request) = self.written.strip_suffix("\x1b[6n") {
+                let grapheme = request.rsplit('\r').next().unwrap();
+                let column = self.widths[grapheme] + 1;
+
+                if !self.silent {
+                    // Some unrelated input that arrived first.
+                    self.responses.extend(b"q\x1b[A");
+                    self.responses
+                        .extend(format!("\x1b[12;{column}R").into_bytes());
+                }
+            }
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_probe() {
+        let mut terminal = FakeTerminal {
+            widths: HashMap::from([("❤️", 2), ("👨‍👩‍👧", 6), ("a", 1)]),
+            ..Default::default()
+        };
+
+        let probe = WidthProbe::new();
+        assert_eq!(probe.probe(&mut terminal, "a").unwrap(), 1);
+        assert!(terminal.written.ends_with("\r\x1b[2K"));
+
+        let mut overrides = WidthOverrides::new();
+        probe
+            .probe_all(&mut terminal, ["❤️", "👨‍👩‍👧"], &mut overrides)
+            .unwrap();
+        assert_eq!(overrides.grapheme("❤️"), Some(2));
+        assert_eq!(overrides.grapheme("👨‍👩‍👧"), Some(6));
+        assert_eq!(overrides.grapheme("a"), None);
+    }
+
+    #[test]
+    fn test_probe_timeout() {
+        let mut terminal = FakeTerminal {
+            widths: HashMap::from([("a", 1)]),
+            silent: true,
+            ..Default::default()
+        };
+
+        let err = WidthProbe::new()
+            .timeout(Duration::from_millis(5))
+            .probe(&mut terminal, "a")
+            .unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::TimedOut);
+    }
+
+    #[test]
+    fn test_parse_report() {
+        assert_eq!(parse_report(b"\x1b[1;3R"), Some(3));
+        assert_eq!(parse_report(b"\x1b[24;80R"), Some(80));
+        assert_eq!(parse_report(b"\x1b[AR"), None);
+        assert_eq!(parse_report(b"\x1b[;3R"), None);
+    }
+}
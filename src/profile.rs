@@ -0,0 +1,324 @@
+//! Presets for how different terminals measure width.
+
+use std::{collections::HashMap, env, ffi::OsStr};
+
+use crate::{CellWidth, WidthOptions};
+
+/// A preset of [`WidthOptions`] approximating how a terminal emulator displays text.
+///
+/// Terminals disagree on the widths of things like emoji joined with a
+/// [zero width joiner](https://unicode-explorer.com/c/200D) or vowel signs in scripts like
+/// Devanagari, so picking the profile for the terminal being drawn to gives more accurate widths.
+/// Each profile covers every terminal that measures text the same way.
+///
+/// ```
+/// use unicode_ellipsis::{str_width_with, Truncator, WidthProfile};
+///
+/// assert_eq!(str_width_with("👨‍👩‍👧", &WidthProfile::Fish.into()), 2);
+/// assert_eq!(str_width_with("👨‍👩‍👧", &WidthProfile::Xterm.into()), 6);
+///
+/// let truncator = Truncator::new(4);
+/// let kitty = truncator.clone().width_options(WidthProfile::Fish.into());
+/// let xterm = truncator.width_options(WidthProfile::Xterm.into());
+/// assert_eq!(kitty.truncate("👨‍👩‍👧 ok"), "👨‍👩‍👧 …");
+/// assert_eq!(xterm.truncate("👨‍👩‍👧 ok"), "…");
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum WidthProfile {
+    /// The widths used by [fish](https://github.com/fish-shell/fish-shell), which is the default
+    /// with the `fish` feature. This also matches [kitty](https://sw.kovidgoyal.net/kitty/) and
+    /// [Windows Terminal](https://github.com/microsoft/terminal).
+    Fish,
+    /// The widths given by `unicode-width`, which is the default without the `fish` feature.
+    /// This also matches [WezTerm](https://wezfurlong.org/wezterm/), which measures whole
+    /// graphemes.
+    UnicodeWidth,
+    /// [xterm](https://invisible-island.net/xterm/), which measures each char on its own and
+    /// ignores emoji joiners and variation selectors. This also matches terminals based on
+    /// [VTE](https://gitlab.gnome.org/GNOME/vte), like GNOME Terminal, and
+    /// [tmux](https://github.com/tmux/tmux) regardless of the terminal it is running in.
+    Xterm,
+}
+
+impl WidthProfile {
+    /// Returns the [`WidthOptions`] for this profile.
+    pub fn options(self) -> WidthOptions {
+        let options = WidthOptions::new();
+
+        match self {
+            WidthProfile::Fish => options.fish_tables(true).emoji_zwj(true),
+            WidthProfile::UnicodeWidth => options.fish_tables(false).emoji_zwj(true),
+            WidthProfile::Xterm => options
+                .fish_tables(true)
+                .emoji_zwj(false)
+                .emoji_presentation(false),
+        }
+    }
+
+    /// Detects the profile of the terminal that is running from environment variables like `TERM`
+    /// and `TERM_PROGRAM`, or [`None`] if the terminal is not recognized.
+    pub fn detect() -> Option<Self> {
+        Self::detect_from(env::vars_os())
+    }
+
+    /// Detects the profile of a terminal like [`WidthProfile::detect`], using `vars` as the
+    /// environment variables.
+    pub fn detect_from<I, K, V>(vars: I) -> Option<Self>
+    where
+        I: IntoIterator<Item = (K, V)>,
+        K: AsRef<OsStr>,
+        V: AsRef<OsStr>,
+    {
+        Env::new(vars).profile()
+    }
+}
+
+impl WidthOptions {
+    /// Detects the [`WidthOptions`] for the terminal that is running from environment variables.
+    ///
+    /// This uses [`WidthProfile::detect`], falling back to [`WidthProfile::Fish`], and treats
+    /// [ambiguous](WidthOptions::ambiguous) characters as wide for Chinese, Japanese and Korean
+    /// locales.
+    pub fn detect() -> Self {
+        Self::detect_from(env::vars_os())
+    }
+
+    /// Detects the [`WidthOptions`] for a terminal like [`WidthOptions::detect`], using `vars` as
+    /// the environment variables.
+    ///
+    /// ```
+    /// use unicode_ellipsis::{CellWidth, WidthOptions, WidthProfile};
+    ///
+    /// let options = WidthOptions::detect_from([("VTE_VERSION", "7600"), ("LANG", "ja_JP.UTF-8")]);
+    /// assert_eq!(options, WidthProfile::Xterm.options().ambiguous(CellWidth::Wide));
+    /// ```
+    pub fn detect_from<I, K, V>(vars: I) -> Self
+    where
+        I: IntoIterator<Item = (K, V)>,
+        K: AsRef<OsStr>,
+        V: AsRef<OsStr>,
+    {
+        let env = Env::new(vars);
+        let mut options = env.profile().unwrap_or(WidthProfile::Fish).options();
+
+        if env.is_cjk_locale() {
+            options = options.ambiguous(CellWidth::Wide);
+        }
+
+        // iTerm2 only uses Unicode 9 widths from version 3 onwards.
+        if env.get("TERM_PROGRAM") == Some("iTerm.app")
+            && env
+                .major_version("TERM_PROGRAM_VERSION")
+                .is_some_and(|major| major < 3)
+        {
+            options = options.widened_in_9(CellWidth::Narrow);
+        }
+
+        options
+    }
+}
+
+/// Environment variables to detect a terminal from.
+struct Env(HashMap<String, String>);
+
+impl Env {
+    fn new<I, K, V>(vars: I) -> Self
+    where
+        I: IntoIterator<Item = (K, V)>,
+        K: AsRef<OsStr>,
+        V: AsRef<OsStr>,
+    {
+        Self(
+            vars.into_iter()
+                .map(|(k, v)| {
+                    (
+                        k.as_ref().to_string_lossy().into_owned(),
+                        v.as_ref().to_string_lossy().into_owned(),
+                    )
+                })
+                .collect(),
+        )
+    }
+
+    /// Returns the value of a variable, treating empty values as unset.
+    fn get(&self, key: &str) -> Option<&str> {
+        self.0
+            .get(key)
+            .map(String::as_str)
+            .filter(|value| !value.is_empty())
+    }
+
+    fn major_version(&self, key: &str) -> Option<u32> {
+        self.get(key)?.split('.').next()?.parse().ok()
+    }
+
+    fn profile(&self) -> Option<WidthProfile> {
+        let term = self.get("TERM").unwrap_or_default();
+        let term_program = self.get("TERM_PROGRAM").unwrap_or_default();
+
+        // tmux measures text itself, so check for it before the terminal it is running in.
+        if self.get("TMUX").is_some() || term_program == "tmux" || term.starts_with("tmux") {
+            Some(WidthProfile::Xterm)
+        } else if self.get("KITTY_WINDOW_ID").is_some() || term == "xterm-kitty" {
+            Some(WidthProfile::Fish)
+        } else if term_program == "WezTerm" || term == "wezterm" {
+            Some(WidthProfile::UnicodeWidth)
+        } else if self.get("WT_SESSION").is_some() {
+            Some(WidthProfile::Fish)
+        } else if self.get("VTE_VERSION").is_some() || self.get("XTERM_VERSION").is_some() {
+            Some(WidthProfile::Xterm)
+        } else {
+            None
+        }
+    }
+
+    fn is_cjk_locale(&self) -> bool {
+        let locale = self
+            .get("LC_ALL")
+            .or_else(|| self.get("LC_CTYPE"))
+            .or_else(|| self.get("LANG"))
+            .unwrap_or_default();
+
+        // Only compare the language, so `kok_IN` (Konkani) isn't mistaken for Korean.
+        let language = locale
+            .split(['_', '.', '-', '@'])
+            .next()
+            .unwrap_or_default();
+        matches!(language, "zh" | "ja" | "ko")
+    }
+}
+
+impl From<WidthProfile> for WidthOptions {
+    #[inline]
+    fn from(profile: WidthProfile) -> Self {
+        profile.options()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{grapheme_width_with, str_width, str_width_with};
+
+    #[test]
+    fn test_profiles() {
+        // cSpell:disable
+        let family = "👨‍👩‍👧";
+        let hindi = "हिन्दी";
+
+        for (profile, family_width, hindi_width) in [
+            (WidthProfile::Fish, 2, 3),
+            (WidthProfile::UnicodeWidth, 2, 5),
+            (WidthProfile::Xterm, 6, 3),
+        ] {
+            let options = profile.into();
+            assert_eq!(
+                grapheme_width_with(family, &options),
+                family_width,
+                "{profile:?}"
+            );
+            assert_eq!(str_width_with(hindi, &options), hindi_width, "{profile:?}");
+        }
+
+        // Each profile should measure text differently from the others.
+        assert_ne!(
+            WidthProfile::Fish.options(),
+            WidthProfile::UnicodeWidth.options()
+        );
+        assert_ne!(WidthProfile::Fish.options(), WidthProfile::Xterm.options());
+        assert_ne!(
+            WidthProfile::UnicodeWidth.options(),
+            WidthProfile::Xterm.options()
+        );
+
+        #[cfg(feature = "fish")]
+        let default = WidthProfile::Fish;
+        #[cfg(not(feature = "fish"))]
+        let default = WidthProfile::UnicodeWidth;
+
+        assert_eq!(str_width(hindi), str_width_with(hindi, &default.into()));
+        // cSpell:enable;
+    }
+
+    #[test]
+    fn test_detect_profile() {
+        for (vars, profile) in [
+            (vec![("TERM", "xterm-kitty")], Some(WidthProfile::Fish)),
+            (
+                vec![("TERM", "xterm-256color"), ("KITTY_WINDOW_ID", "3")],
+                Some(WidthProfile::Fish),
+            ),
+            (
+                vec![("TERM_PROGRAM", "WezTerm"), ("TERM", "xterm-256color")],
+                Some(WidthProfile::UnicodeWidth),
+            ),
+            (vec![("WT_SESSION", "abc")], Some(WidthProfile::Fish)),
+            (vec![("VTE_VERSION", "7600")], Some(WidthProfile::Xterm)),
+            (
+                vec![("XTERM_VERSION", "XTerm(390)")],
+                Some(WidthProfile::Xterm),
+            ),
+            (
+                vec![("TERM", "tmux-256color"), ("KITTY_WINDOW_ID", "3")],
+                Some(WidthProfile::Xterm),
+            ),
+            (
+                vec![
+                    ("TMUX", "/tmp/tmux-1000/default,1,0"),
+                    ("WT_SESSION", "abc"),
+                ],
+                Some(WidthProfile::Xterm),
+            ),
+            (vec![("TERM", "xterm-256color")], None),
+            (vec![("WT_SESSION", "")], None),
+            (vec![], None),
+        ] {
+            assert_eq!(WidthProfile::detect_from(vars.clone()), profile, "{vars:?}");
+        }
+    }
+
+    #[test]
+    fn test_detect_options() {
+        assert_eq!(
+            WidthOptions::detect_from(Vec::<(&str, &str)>::new()),
+            WidthProfile::Fish.options()
+        );
+        assert_eq!(
+            WidthOptions::detect_from([("VTE_VERSION", "7600"), ("LANG", "zh_CN.UTF-8")]),
+            WidthProfile::Xterm.options().ambiguous(CellWidth::Wide)
+        );
+        assert_eq!(
+            WidthOptions::detect_from([("LC_ALL", "en_US.UTF-8"), ("LANG", "ko_KR.UTF-8")]),
+            WidthProfile::Fish.options(),
+            "LC_ALL should take precedence over LANG"
+        );
+        assert_eq!(
+            WidthOptions::detect_from([("LANG", "ja")]),
+            WidthProfile::Fish.options().ambiguous(CellWidth::Wide)
+        );
+        assert_eq!(
+            WidthOptions::detect_from([("LC_CTYPE", "zh-Hant@stroke")]),
+            WidthProfile::Fish.options().ambiguous(CellWidth::Wide)
+        );
+        assert_eq!(
+            WidthOptions::detect_from([("LANG", "kok_IN.UTF-8")]),
+            WidthProfile::Fish.options(),
+            "only the whole language code should be compared"
+        );
+        assert_eq!(
+            WidthOptions::detect_from([
+                ("TERM_PROGRAM", "iTerm.app"),
+                ("TERM_PROGRAM_VERSION", "2.1.4")
+            ]),
+            WidthProfile::Fish.options().widened_in_9(CellWidth::Narrow)
+        );
+        assert_eq!(
+            WidthOptions::detect_from([
+                ("TERM_PROGRAM", "iTerm.app"),
+                ("TERM_PROGRAM_VERSION", "3.5.0")
+            ]),
+            WidthProfile::Fish.options()
+        );
+    }
+}
@@ -0,0 +1,557 @@
+//! A reusable set of truncation options.
+
+use std::borrow::Cow;
+
+use unicode_segmentation::UnicodeSegmentation;
+
+use crate::{
+    control::Visible, cut_middle, cut_sided, expand_tabs, ControlChars, Cut, Ellipsis, Measure,
+    Taken, TruncationInfo, WidthOptions,
+};
+
+/// Which side of a string is cut off when truncating, and therefore where the ellipsis goes.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum Side {
+    /// Cut off the end of the string, like [`truncate_str`](crate::truncate_str).
+    #[default]
+    Trailing,
+    /// Cut off the start of the string, like [`truncate_str_leading`](crate::truncate_str_leading).
+    Leading,
+    /// Cut out the middle of the string, like [`truncate_str_middle`](crate::truncate_str_middle).
+    Middle,
+}
+
+/// Where a string is allowed to be cut when truncating.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum Boundary {
+    /// Cut between any two [graphemes](https://www.unicode.org/reports/tr29/#Grapheme_Cluster_Boundaries).
+    #[default]
+    Grapheme,
+    /// Prefer cutting at a [word boundary](https://www.unicode.org/reports/tr29/#Word_Boundaries),
+    /// trimming any whitespace or punctuation next to the ellipsis. For example,
+    /// `"Fix race in watcher initialization"` becomes `"Fix race in…"` rather than
+    /// `"Fix race in watc…"`.
+    ///
+    /// If not even a single word fits, this falls back to cutting between graphemes.
+    Word,
+}
+
+/// A reusable set of truncation options, for when the same truncation is done many times.
+///
+/// Things like the width of the ellipsis are computed once when the [`Truncator`] is built rather than on
+/// every call, and cloning one is cheap as long as the ellipsis is a `&'static str`.
+///
+/// By default, widths are measured in terminal columns with [`WidthOptions`]. Any other
+/// [`Measure`] can be used with [`Truncator::with_measure`].
+///
+/// ```
+/// use unicode_ellipsis::{Side, Truncator};
+///
+/// let truncator = Truncator::new(8).side(Side::Middle).ellipsis("...");
+/// assert_eq!(truncator.truncate("coredns-7db6d8ff4d"), "cor...4d");
+/// ```
+#[derive(Clone, Debug, PartialEq)]
+pub struct Truncator<M: Measure = WidthOptions> {
+    width: M::Width,
+    side: Side,
+    middle_bias: f32,
+    ellipsis: Cow<'static, str>,
+    ellipsis_width: M::Width,
+    boundary: Boundary,
+    expand_tabs: bool,
+    control_chars: ControlChars,
+    measure: M,
+}
+
+impl Default for Truncator {
+    fn default() -> Self {
+        Self::new(0)
+    }
+}
+
+impl Truncator {
+    /// Creates a new [`Truncator`] that truncates to `width` with a trailing `…`, cutting between
+    /// graphemes.
+    pub fn new(width: usize) -> Self {
+        Self {
+            width,
+            side: Side::Trailing,
+            middle_bias: 0.5,
+            ellipsis: Cow::Borrowed(Ellipsis::DEFAULT.text),
+            ellipsis_width: Ellipsis::DEFAULT.width,
+            boundary: Boundary::Grapheme,
+            expand_tabs: false,
+            control_chars: ControlChars::Keep,
+            measure: WidthOptions::new(),
+        }
+    }
+
+    /// Sets how the widths of the string and the ellipsis are measured.
+    pub fn width_options(self, options: WidthOptions) -> Self {
+        self.measure(options)
+    }
+}
+
+impl<M: Measure> Truncator<M> {
+    /// Creates a new [`Truncator`] like [`Truncator::new`], measuring widths with `measure`.
+    pub fn with_measure(width: M::Width, measure: M) -> Self {
+        Self {
+            width,
+            side: Side::Trailing,
+            middle_bias: 0.5,
+            ellipsis: Cow::Borrowed(Ellipsis::DEFAULT.text),
+            ellipsis_width: measure.str_width(Ellipsis::DEFAULT.text),
+            boundary: Boundary::Grapheme,
+            expand_tabs: false,
+            control_chars: ControlChars::Keep,
+            measure,
+        }
+    }
+
+    /// Sets the width to truncate to.
+    pub fn width(mut self, width: M::Width) -> Self {
+        self.width = width;
+        self
+    }
+
+    /// Sets which side of the string is cut off.
+    pub fn side(mut self, side: Side) -> Self {
+        self.side = side;
+        self
+    }
+
+    /// Sets the fraction of the available width given to the start of the string when truncating
+    /// with [`Side::Middle`]. See [`truncate_str_middle_with_bias`](crate::truncate_str_middle_with_bias).
+    pub fn middle_bias(mut self, bias: f32) -> Self {
+        self.middle_bias = bias;
+        self
+    }
+
+    /// Sets the ellipsis to use. Its width is measured the same way as the string.
+    pub fn ellipsis(mut self, ellipsis: impl Into<Cow<'static, str>>) -> Self {
+        self.ellipsis = ellipsis.into();
+        self.ellipsis_width = self.measure.str_width(&self.ellipsis);
+        self
+    }
+
+    /// Sets where the string is allowed to be cut.
+    pub fn boundary(mut self, boundary: Boundary) -> Self {
+        self.boundary = boundary;
+        self
+    }
+
+    /// Sets whether tabs in the output are replaced with spaces up to the next tab stop, so that
+    /// the output looks the same wherever it is displayed.
+    ///
+    /// Tabs are replaced with as many spaces as they are wide, so this is meant for when widths are
+    /// measured in columns.
+    pub fn expand_tabs(mut self, enabled: bool) -> Self {
+        self.expand_tabs = enabled;
+        self
+    }
+
+    /// Sets how control characters in the string are shown. Replacing them with something visible
+    /// ensures the output is as wide as measured, even if the string comes from an untrusted
+    /// source.
+    ///
+    /// ```
+    /// use unicode_ellipsis::{ControlChars, Truncator};
+    ///
+    /// let truncator = Truncator::new(8).control_chars(ControlChars::Caret);
+    /// assert_eq!(truncator.truncate("done\r\n"), "done^M^J");
+    /// assert_eq!(truncator.truncate("\x1b[2Jcleared"), "^[[2Jcl…");
+    /// ```
+    pub fn control_chars(mut self, control_chars: ControlChars) -> Self {
+        self.control_chars = control_chars;
+        self
+    }
+
+    /// Sets how the widths of the string and the ellipsis are measured.
+    pub fn measure(mut self, measure: M) -> Self {
+        self.ellipsis_width = measure.str_width(&self.ellipsis);
+        self.measure = measure;
+        self
+    }
+
+    /// Truncates `content` with the current options.
+    pub fn truncate<'a>(&self, content: &'a str) -> Cow<'a, str> {
+        self.output(content, self.cut(content))
+    }
+
+    /// Truncates `content` with the current options, returning a [`TruncationInfo`] describing
+    /// what was kept.
+    pub fn truncate_with_info<'a>(&self, content: &'a str) -> TruncationInfo<'a, M::Width> {
+        let cut = self.cut(content);
+
+        TruncationInfo::with_output(
+            content,
+            cut,
+            self.output(content, cut),
+            self.ellipsis_ref(),
+            self.width,
+            &self.visible(),
+        )
+    }
+
+    /// Builds the output for `content` being truncated as described by `cut`, replacing any
+    /// control characters and expanding tabs if needed.
+    fn output<'a>(&self, content: &'a str, cut: Option<Cut<M::Width>>) -> Cow<'a, str> {
+        let output = match cut {
+            Some(cut) if self.control_chars == ControlChars::Keep => cut
+                .join(content, self.ellipsis_ref(), self.width, &self.measure)
+                .into(),
+            Some(cut) => {
+                let (head, tail) = cut.parts(content);
+                let ellipsis = self.ellipsis_ref().fit(self.width, &self.measure);
+
+                [
+                    &*self.control_chars.visualize(head),
+                    ellipsis,
+                    &*self.control_chars.visualize(tail),
+                ]
+                .concat()
+                .into()
+            }
+            None => self.control_chars.visualize(content),
+        };
+
+        if self.expand_tabs && output.contains('\t') {
+            expand_tabs(&output, M::Width::default(), &self.measure).into()
+        } else {
+            output
+        }
+    }
+
+    #[inline]
+    fn ellipsis_ref(&self) -> Ellipsis<'_, M::Width> {
+        Ellipsis {
+            text: &self.ellipsis,
+            width: self.ellipsis_width,
+        }
+    }
+
+    /// Returns the measure to use for the string, which measures any control characters by what
+    /// replaces them.
+    #[inline]
+    fn visible(&self) -> Visible<&M> {
+        Visible {
+            measure: &self.measure,
+            control_chars: self.control_chars,
+        }
+    }
+
+    /// Works out what to keep from `content`, or [`None`] if it fits.
+    fn cut(&self, content: &str) -> Option<Cut<M::Width>> {
+        let ellipsis = self.ellipsis_ref();
+        let measure = &self.visible();
+
+        let cut = match self.side {
+            Side::Trailing => cut_sided::<false, _>(content, self.width, ellipsis, measure),
+            Side::Leading => cut_sided::<true, _>(content, self.width, ellipsis, measure),
+            Side::Middle => cut_middle(content, self.width, self.middle_bias, ellipsis, measure),
+        };
+
+        match self.boundary {
+            Boundary::Grapheme => cut,
+            Boundary::Word => cut.map(|cut| snap_to_words(content, cut, measure)),
+        }
+    }
+}
+
+/// Whether a word-bounded segment is an actual word, rather than whitespace or punctuation.
+#[inline]
+fn is_word(segment: &str) -> bool {
+    segment.chars().any(char::is_alphanumeric)
+}
+
+/// Shrinks what a [`Cut`] keeps so that it only keeps whole words, unless that would mean keeping
+/// nothing from a side.
+fn snap_to_words<M: Measure>(content: &str, cut: Cut<M::Width>, measure: &M) -> Cut<M::Width> {
+    let mut head = cut.head;
+    let mut tail = cut.tail;
+
+    if head.bytes > 0 {
+        // Find the end of the last word that is entirely kept.
+        let mut end = 0;
+        for (start, segment) in content.split_word_bound_indices() {
+            let segment_end = start + segment.len();
+            if segment_end > head.bytes {
+                break;
+            } else if is_word(segment) {
+                end = segment_end;
+            }
+        }
+
+        if end > 0 {
+            head = Taken {
+                bytes: end,
+                width: measure.str_width(&content[..end]),
+            };
+        }
+    }
+
+    if tail.bytes > 0 {
+        // Find the start of the first word that is entirely kept.
+        let tail_start = content.len() - tail.bytes;
+        let mut start = content.len();
+        for (segment_start, segment) in content.split_word_bound_indices().rev() {
+            if segment_start < tail_start {
+                break;
+            } else if is_word(segment) {
+                start = segment_start;
+            }
+        }
+
+        if start < content.len() {
+            tail = Taken {
+                bytes: content.len() - start,
+                width: tail.width - measure.str_width(&content[tail_start..start]),
+            };
+        }
+    }
+
+    Cut { head, tail }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{truncate_str, truncate_str_leading, truncate_str_middle};
+
+    #[test]
+    fn test_matches_functions() {
+        let tests = [
+            "0123456789",
+            "施氏食獅史",
+            "Test (施氏abc食abc獅史) Test",
+            "🇨🇦加gaa拿naa大daai🇨🇦",
+        ];
+
+        for content in tests {
+            for width in 0..25 {
+                let truncator = Truncator::new(width);
+
+                assert_eq!(
+                    truncator.truncate(content),
+                    truncate_str(content, width),
+                    "trailing should match for {content:?} at {width}"
+                );
+                assert_eq!(
+                    truncator.clone().side(Side::Leading).truncate(content),
+                    truncate_str_leading(content, width),
+                    "leading should match for {content:?} at {width}"
+                );
+                assert_eq!(
+                    truncator.clone().side(Side::Middle).truncate(content),
+                    truncate_str_middle(content, width),
+                    "middle should match for {content:?} at {width}"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_options() {
+        let truncator = Truncator::new(6).ellipsis("...");
+        assert_eq!(truncator.truncate("0123456789"), "012...");
+        assert_eq!(truncator.truncate("012345"), "012345");
+
+        let truncator = truncator.side(Side::Leading);
+        assert_eq!(truncator.truncate("0123456789"), "...789");
+
+        let truncator = truncator.side(Side::Middle).middle_bias(0.0).width(5);
+        assert_eq!(truncator.truncate("0123456789"), "...89");
+
+        let truncator = Truncator::new(5).ellipsis(String::from("︙"));
+        assert_eq!(truncator.truncate("施氏食獅史"), "施︙");
+    }
+
+    #[test]
+    fn test_truncate_with_info() {
+        let truncator = Truncator::new(6).ellipsis("...").side(Side::Leading);
+
+        let info = truncator.truncate_with_info("0123456789");
+        assert_eq!(info.output, "...789");
+        assert_eq!(info.head, 0..0);
+        assert_eq!(info.tail, 7..10);
+        assert_eq!(info.width, 6);
+        assert_eq!(info.graphemes_dropped, 7);
+        assert!(info.was_truncated);
+
+        let info = truncator.width(2).truncate_with_info("0123456789");
+        assert_eq!(info.output, "..");
+        assert_eq!(info.width, 2);
+        assert_eq!(info.graphemes_dropped, 10);
+    }
+
+    #[test]
+    fn test_word_boundary() {
+        let truncator = Truncator::new(20).boundary(Boundary::Word);
+        let title = "Fix race in watcher initialization";
+
+        assert_eq!(truncator.truncate(title), "Fix race in watcher…");
+        assert_eq!(truncator.clone().width(17).truncate(title), "Fix race in…");
+        assert_eq!(truncator.clone().width(50).truncate(title), title);
+
+        assert_eq!(
+            truncator.clone().width(14).truncate("Hello, world! Again"),
+            "Hello, world…",
+            "trailing punctuation should be trimmed"
+        );
+        assert_eq!(
+            truncator
+                .clone()
+                .width(6)
+                .truncate("Supercalifragilistic word"),
+            "Super…",
+            "should fall back to graphemes if the first word does not fit"
+        );
+        assert_eq!(
+            truncator.clone().width(5).truncate("施氏食獅史"),
+            "施氏…",
+            "each ideograph is its own word"
+        );
+    }
+
+    #[test]
+    fn test_word_boundary_leading() {
+        let truncator = Truncator::new(10)
+            .side(Side::Leading)
+            .boundary(Boundary::Word);
+
+        assert_eq!(truncator.truncate("Fix race in watcher"), "…watcher");
+        assert_eq!(
+            truncator
+                .clone()
+                .width(6)
+                .truncate("word Supercalifragilistic"),
+            "…istic"
+        );
+
+        let info = truncator.truncate_with_info("Fix race in watcher");
+        assert_eq!(info.tail, 12..19);
+        assert_eq!(info.width, 8);
+        assert_eq!(info.graphemes_dropped, 12);
+    }
+
+    #[test]
+    fn test_word_boundary_middle() {
+        let truncator = Truncator::new(13)
+            .side(Side::Middle)
+            .boundary(Boundary::Word);
+
+        assert_eq!(truncator.truncate("alpha beta gamma delta"), "alpha…delta");
+    }
+
+    #[test]
+    fn test_width_options() {
+        let wide = WidthOptions::new().ambiguous(crate::CellWidth::Wide);
+        let content = "±1 §2 ①3";
+
+        let truncator = Truncator::new(6);
+        assert_eq!(truncator.truncate(content), "±1 §2…");
+
+        let truncator = truncator.width_options(wide.clone());
+        assert_eq!(truncator.truncate(content), "±1 …");
+        assert_eq!(
+            truncator.clone().side(Side::Leading).truncate(content),
+            "… ①3"
+        );
+
+        let truncator = Truncator::new(4).ellipsis("§").width_options(wide);
+        assert_eq!(
+            truncator.truncate(content),
+            "±§",
+            "the ellipsis should be measured with the width options too"
+        );
+    }
+
+    #[test]
+    fn test_expand_tabs() {
+        let truncator = Truncator::new(10).expand_tabs(true);
+        assert_eq!(truncator.truncate("a\tb"), "a       b");
+        assert_eq!(truncator.truncate("ab\tcdefgh"), "ab      c…");
+        assert_eq!(truncator.truncate("no tabs"), "no tabs");
+
+        let info = truncator.truncate_with_info("ab\tcdefgh");
+        assert_eq!(info.output, "ab      c…");
+        assert_eq!(info.head, 0..4);
+
+        let truncator = truncator.side(Side::Leading);
+        assert_eq!(truncator.truncate("abcdefgh\tij"), "…ij");
+        assert_eq!(truncator.truncate("abcdef\tgh"), "abcdef  gh");
+
+        let truncator = Truncator::new(10)
+            .width_options(WidthOptions::new().tab_width(4))
+            .expand_tabs(true);
+        assert_eq!(truncator.truncate("a\tb\tcdef"), "a   b   c…");
+        assert_eq!(
+            Truncator::new(10).truncate("a\tb"),
+            "a\tb",
+            "tabs should be kept by default"
+        );
+    }
+
+    #[test]
+    fn test_control_chars() {
+        let truncator = Truncator::new(3).control_chars(ControlChars::Caret);
+        assert_eq!(truncator.truncate("a\rb"), "a…");
+        assert_eq!(truncator.truncate("\r"), "^M");
+        assert_eq!(
+            truncator.clone().width(9).truncate("a\tb"),
+            "a\tb",
+            "tabs should be kept"
+        );
+
+        let info = truncator.truncate_with_info("a\rb");
+        assert_eq!(info.head, 0..1);
+        assert_eq!(info.width, 2);
+
+        let truncator = truncator.width(4).side(Side::Leading);
+        assert_eq!(truncator.truncate("abc\r"), "…c^M");
+        assert_eq!(truncator.truncate_with_info("abc\r").tail, 2..4);
+
+        let truncator = Truncator::new(4).control_chars(ControlChars::Pictures);
+        assert_eq!(truncator.truncate("a\rbc"), "a␍bc");
+        assert_eq!(truncator.truncate("a\r\nbc"), "a␍␊…");
+
+        let truncator = Truncator::new(8).control_chars(ControlChars::Escape);
+        assert_eq!(truncator.truncate("\x1b[31mred"), "\\u{1b}[…");
+        assert_eq!(
+            truncator.clone().side(Side::Middle).truncate("\x1b[31mred"),
+            "…[31mred"
+        );
+
+        assert_eq!(
+            Truncator::new(3).truncate("a\rb"),
+            "a\rb",
+            "control characters should be kept by default"
+        );
+    }
+
+    #[test]
+    fn test_width_overrides() {
+        let mut overrides = crate::WidthOverrides::new();
+        overrides.insert_grapheme("❤️", 1);
+        overrides.insert_grapheme("🇨🇦", 4);
+
+        let truncator = Truncator::new(5).width_options(WidthOptions::new().overrides(overrides));
+        assert_eq!(truncator.truncate("❤️❤️❤️❤️❤️"), "❤️❤️❤️❤️❤️");
+        assert_eq!(truncator.truncate("🇨🇦🇨🇦"), "🇨🇦…");
+
+        let overrides: crate::WidthOverrides = "U+E0A0 2\nU+2D 2".parse().unwrap();
+        let truncator = Truncator::new(5).width_options(WidthOptions::new().overrides(overrides));
+        assert_eq!(truncator.truncate("\u{e0a0}-ab"), "\u{e0a0}-…");
+        assert_eq!(truncator.truncate("a--b"), "a-…");
+        assert_eq!(
+            truncator.clone().side(Side::Leading).truncate("a--b"),
+            "…-b"
+        );
+        assert_eq!(
+            truncator.truncate("---"),
+            "--…",
+            "should not fit just because it is short"
+        );
+    }
+}
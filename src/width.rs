@@ -1,17 +1,205 @@
 //! Helper functions related to string or grapheme width.
 
+use std::sync::Arc;
+
 use unicode_segmentation::UnicodeSegmentation;
+use unicode_width::UnicodeWidthChar;
+
+use crate::{
+    char_width_class,
+    emoji::{is_emoji_zwj_sequence, is_extended_pictographic, is_keycap_base},
+    CharWidthClass, Measure, WidthOverrides,
+};
+
+/// How wide to treat characters that can be displayed as either narrow or wide.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum CellWidth {
+    /// Treat the characters as taking up one column.
+    #[default]
+    Narrow,
+    /// Treat the characters as taking up two columns.
+    Wide,
+}
+
+impl CellWidth {
+    /// The number of columns taken up.
+    #[inline]
+    const fn columns(self) -> usize {
+        match self {
+            CellWidth::Narrow => 1,
+            CellWidth::Wide => 2,
+        }
+    }
+}
+
+/// Options controlling how string and grapheme widths are measured, for use with functions like
+/// [`str_width_with`] and [`Truncator::width_options`](crate::Truncator::width_options).
+///
+/// The default options match [`str_width`] and [`grapheme_width`].
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct WidthOptions {
+    ambiguous: CellWidth,
+    widened_in_9: CellWidth,
+    private_use: CellWidth,
+    fish_tables: bool,
+    emoji_zwj: bool,
+    emoji_presentation: bool,
+    tab_width: usize,
+    overrides: Option<Arc<WidthOverrides>>,
+}
+
+impl WidthOptions {
+    /// The default options.
+    pub(crate) const DEFAULT: WidthOptions = WidthOptions::new();
+
+    /// Creates a new set of default [`WidthOptions`].
+    pub const fn new() -> Self {
+        Self {
+            ambiguous: CellWidth::Narrow,
+            widened_in_9: CellWidth::Wide,
+            private_use: CellWidth::Narrow,
+            fish_tables: cfg!(feature = "fish"),
+            emoji_zwj: true,
+            emoji_presentation: true,
+            tab_width: 8,
+            overrides: None,
+        }
+    }
+
+    /// Sets how wide [East Asian Ambiguous](https://www.unicode.org/reports/tr11/#Ambiguous)
+    /// characters like `±`, `§` or `①` are. These are usually narrow, but terminals in CJK locales
+    /// often display them as wide. Defaults to [`CellWidth::Narrow`].
+    pub fn ambiguous(mut self, width: CellWidth) -> Self {
+        self.ambiguous = width;
+        self
+    }
+
+    /// Sets how wide characters that were widened to two columns in Unicode 9, like `⌚` or `💎`,
+    /// are. Terminals based on an older `wcwidth` still display these as narrow. Defaults to
+    /// [`CellWidth::Wide`].
+    ///
+    /// This only has an effect if [`fish_tables`](Self::fish_tables) is enabled.
+    pub fn widened_in_9(mut self, width: CellWidth) -> Self {
+        self.widened_in_9 = width;
+        self
+    }
+
+    /// Sets how wide private use characters, like [Nerd Font](https://www.nerdfonts.com/) icons,
+    /// are. Defaults to [`CellWidth::Narrow`].
+    ///
+    /// This only has an effect if [`fish_tables`](Self::fish_tables) is enabled.
+    pub fn private_use(mut self, width: CellWidth) -> Self {
+        self.private_use = width;
+        self
+    }
+
+    /// Sets whether to measure chars with the [widecharwidth](https://github.com/ridiculousfish/widecharwidth)
+    /// tables used by [fish](https://github.com/fish-shell/fish-shell), rather than only with
+    /// `unicode-width`. Among other things, this treats vowel signs in scripts like Devanagari as
+    /// zero width. Defaults to whether the `fish` feature is enabled.
+    pub fn fish_tables(mut self, enabled: bool) -> Self {
+        self.fish_tables = enabled;
+        self
+    }
+
+    /// Sets whether emoji joined with a [zero width joiner](https://unicode-explorer.com/c/200D),
+    /// like `👨‍👩‍👧`, are displayed as a single two column wide emoji. If disabled, the widths of
+    /// the joined chars are added up instead. Joiners between other chars never change their
+    /// width. Defaults to `true`.
+    pub fn emoji_zwj(mut self, enabled: bool) -> Self {
+        self.emoji_zwj = enabled;
+        self
+    }
+
+    /// Sets whether [variation selectors](https://unicode-explorer.com/c/FE0F) change how wide an
+    /// emoji is. If enabled, U+FE0F makes chars like `❤` and keycaps like `1️⃣` two columns wide,
+    /// and U+FE0E makes emoji like `⌚︎` one column wide. Defaults to `true`.
+    pub fn emoji_presentation(mut self, enabled: bool) -> Self {
+        self.emoji_presentation = enabled;
+        self
+    }
+
+    /// Sets how many columns apart tab stops are. A tab extends to the next tab stop, so it is
+    /// between 1 and this many columns wide depending on where it starts, or exactly this many
+    /// columns wide if that isn't known. Tabs are always measured this way rather than with any
+    /// [overrides](Self::overrides), and setting this to 0 treats them as zero width. Defaults
+    /// to 8.
+    pub fn tab_width(mut self, width: usize) -> Self {
+        self.tab_width = width;
+        self
+    }
+
+    /// Sets widths to use instead of the built-in ones, for example to match a patched font or
+    /// widths measured from the terminal itself.
+    pub fn overrides(mut self, overrides: impl Into<Arc<WidthOverrides>>) -> Self {
+        self.overrides = Some(overrides.into());
+        self
+    }
+}
+
+impl Measure for WidthOptions {
+    type Width = usize;
 
-#[cfg(feature = "fish")]
-use crate::widecharwidth::char_width;
+    #[inline]
+    fn grapheme_width(&self, grapheme: &str) -> usize {
+        grapheme_width_with(grapheme, self)
+    }
+
+    #[inline]
+    fn grapheme_width_at(&self, grapheme: &str, column: usize) -> usize {
+        grapheme_width_at_with(grapheme, column, self)
+    }
+
+    #[inline]
+    fn str_width(&self, s: &str) -> usize {
+        str_width_with(s, self)
+    }
+
+    #[inline]
+    fn is_byte_bounded(&self) -> bool {
+        self.overrides
+            .as_ref()
+            .is_none_or(|overrides| overrides.is_byte_bounded())
+    }
+}
 
 /// Returns the width of a str `s`, breaking the string down into multiple [graphemes](https://www.unicode.org/reports/tr29/#Grapheme_Cluster_Boundaries).
 /// This takes into account some things like [joiners](https://unicode-explorer.com/c/200D) when calculating width.
+///
+/// Tabs extend to the next tab stop, assuming `s` starts at the start of a line; see
+/// [`str_width_at`] otherwise.
 #[inline]
 pub fn str_width(s: &str) -> usize {
-    UnicodeSegmentation::graphemes(s, true)
-        .map(grapheme_width)
-        .sum()
+    str_width_with(s, &WidthOptions::DEFAULT)
+}
+
+/// Returns the width of a str `s` like [`str_width`], measured with the given [`WidthOptions`].
+#[inline]
+pub fn str_width_with(s: &str, options: &WidthOptions) -> usize {
+    str_width_at_with(s, 0, options)
+}
+
+/// Returns the width of a str `s` like [`str_width`], where `s` starts `column` columns into a
+/// line. This only makes a difference if `s` contains tabs.
+///
+/// ```
+/// use unicode_ellipsis::{str_width, str_width_at};
+///
+/// assert_eq!(str_width("ab\tc"), 9);
+/// assert_eq!(str_width_at("ab\tc", 3), 6);
+/// ```
+#[inline]
+pub fn str_width_at(s: &str, column: usize) -> usize {
+    str_width_at_with(s, column, &WidthOptions::DEFAULT)
+}
+
+/// Returns the width of a str `s` like [`str_width_at`], measured with the given
+/// [`WidthOptions`].
+#[inline]
+pub fn str_width_at_with(s: &str, column: usize, options: &WidthOptions) -> usize {
+    UnicodeSegmentation::graphemes(s, true).fold(0, |width, g| {
+        width + grapheme_width_at_with(g, column + width, options)
+    })
 }
 
 /// Returns the width of a single grapheme `g`. This takes into account some things like
@@ -20,31 +208,121 @@ pub fn str_width(s: &str) -> usize {
 /// Note that while you *can* pass in an entire string, this function assumes you are passing in
 /// just a single grapheme (e.g. `"a"`, `"💎"`, `"大"`, `"🇨🇦"`), and therefore makes no attempt in
 /// splitting the string into its individual graphemes.
+///
+/// As where it starts isn't known, a tab is treated as being as wide as a full tab stop.
 #[inline]
 pub fn grapheme_width(g: &str) -> usize {
-    if g.contains('\u{200d}') {
-        2
+    grapheme_width_with(g, &WidthOptions::DEFAULT)
+}
+
+/// Returns the width of a single grapheme `g` like [`grapheme_width`], measured with the given
+/// [`WidthOptions`].
+#[inline]
+pub fn grapheme_width_with(g: &str, options: &WidthOptions) -> usize {
+    if g == "\t" {
+        return options.tab_width;
+    }
+
+    if let Some(overrides) = &options.overrides {
+        if let Some(width) = overrides.grapheme(g) {
+            return width;
+        } else if overrides.has_chars() && g.chars().any(|c| overrides.char(c).is_some()) {
+            return g.chars().map(|c| char_width_with(c, options)).sum();
+        }
+    }
+
+    if is_emoji_zwj_sequence(g) {
+        if options.emoji_zwj {
+            2
+        } else {
+            g.chars().map(|c| char_width_with(c, options)).sum()
+        }
+    } else if let Some(width) = presentation_width(g) {
+        if options.emoji_presentation {
+            width
+        } else {
+            g.chars().map(|c| char_width_with(c, options)).sum()
+        }
+    } else if options.fish_tables {
+        g.chars().map(|c| char_width_with(c, options)).sum()
     } else {
-        #[cfg(feature = "fish")]
-        {
-            use unicode_width::UnicodeWidthChar;
-            g.chars()
-                .map(|c| {
-                    if let Some(w) = char_width(c) {
-                        w
-                    } else {
-                        UnicodeWidthChar::width(c).unwrap_or(0)
-                    }
-                })
-                .sum()
+        use unicode_width::UnicodeWidthStr;
+        match options.ambiguous {
+            CellWidth::Narrow => UnicodeWidthStr::width(g),
+            CellWidth::Wide => UnicodeWidthStr::width_cjk(g),
         }
+    }
+}
 
-        #[cfg(not(feature = "fish"))]
-        {
-            use unicode_width::UnicodeWidthStr;
-            UnicodeWidthStr::width(g)
+/// Returns the width of a single grapheme `g` that starts `column` columns into a line.
+#[inline]
+fn grapheme_width_at_with(g: &str, column: usize, options: &WidthOptions) -> usize {
+    match (g, options.tab_width) {
+        ("\t", 0) => 0,
+        ("\t", tab_width) => tab_width - column % tab_width,
+        _ => grapheme_width_with(g, options),
+    }
+}
+
+/// Returns the width of a grapheme `g` if it is an emoji followed by a variation selector, which
+/// picks whether it is displayed as an emoji (U+FE0F) or as text (U+FE0E).
+#[inline]
+fn presentation_width(g: &str) -> Option<usize> {
+    let mut chars = g.chars();
+    let base = chars.next()?;
+
+    match chars.next()? {
+        '\u{fe0f}' if is_extended_pictographic(base) || is_keycap_base(base) => Some(2),
+        '\u{fe0e}' if is_extended_pictographic(base) => Some(1),
+        _ => None,
+    }
+}
+
+/// Returns the width of a single char `c`. Characters that are not printed, like control
+/// characters, have a width of 0, while tabs are as wide as a full tab stop; see [`char_width_class`](crate::char_width_class) to tell these
+/// apart.
+///
+/// Prefer [`grapheme_width`] when measuring text, as a grapheme's width is not always the sum of
+/// its chars' widths.
+#[inline]
+pub fn char_width(c: char) -> usize {
+    char_width_with(c, &WidthOptions::DEFAULT)
+}
+
+/// Returns the width of a single char `c` like [`char_width`], measured with the given
+/// [`WidthOptions`].
+#[inline]
+pub fn char_width_with(c: char, options: &WidthOptions) -> usize {
+    if c == '\t' {
+        return options.tab_width;
+    }
+
+    if let Some(width) = options
+        .overrides
+        .as_ref()
+        .and_then(|overrides| overrides.char(c))
+    {
+        return width;
+    }
+
+    if options.fish_tables {
+        match char_width_class(c) {
+            CharWidthClass::NonPrint
+            | CharWidthClass::Combining
+            | CharWidthClass::CombiningLetter
+            | CharWidthClass::NonChar => return 0,
+            CharWidthClass::Ambiguous => return options.ambiguous.columns(),
+            CharWidthClass::WidenedIn9 => return options.widened_in_9.columns(),
+            CharWidthClass::PrivateUse => return options.private_use.columns(),
+            CharWidthClass::Narrow | CharWidthClass::Wide | CharWidthClass::Unassigned => {}
         }
     }
+
+    match options.ambiguous {
+        CellWidth::Narrow => UnicodeWidthChar::width(c),
+        CellWidth::Wide => UnicodeWidthChar::width_cjk(c),
+    }
+    .unwrap_or(0)
 }
 
 #[cfg(test)]
@@ -90,4 +368,169 @@ mod test {
         assert_eq!(grapheme_width("हि"), 2);
         // cSpell:enable;
     }
+
+    #[test]
+    fn test_char_width() {
+        assert_eq!(char_width('a'), 1);
+        assert_eq!(char_width('大'), 2);
+        assert_eq!(char_width('💎'), 2);
+        assert_eq!(char_width('\u{7}'), 0);
+        assert_eq!(char_width('\u{301}'), 0);
+        assert_eq!(char_width('±'), 1);
+        assert_eq!(
+            char_width_with('±', &WidthOptions::new().ambiguous(CellWidth::Wide)),
+            2
+        );
+
+        #[cfg(feature = "fish")]
+        assert_eq!(char_width('\u{1160}'), 0);
+    }
+
+    #[test]
+    fn test_variation_selectors() {
+        // Text-default emoji are widened by VS16.
+        assert_eq!(grapheme_width("❤"), 1);
+        assert_eq!(grapheme_width("❤\u{fe0f}"), 2);
+        assert_eq!(grapheme_width("❤\u{fe0e}"), 1);
+        assert_eq!(grapheme_width("©\u{fe0f}"), 2);
+
+        // Emoji-default emoji are narrowed by VS15.
+        assert_eq!(grapheme_width("⌚"), 2);
+        assert_eq!(grapheme_width("⌚\u{fe0f}"), 2);
+        assert_eq!(grapheme_width("⌚\u{fe0e}"), 1);
+        assert_eq!(grapheme_width("🙂\u{fe0e}"), 1);
+
+        // Keycaps are only emoji with VS16.
+        assert_eq!(grapheme_width("1\u{fe0f}\u{20e3}"), 2);
+        assert_eq!(grapheme_width("#\u{fe0f}\u{20e3}"), 2);
+        assert_eq!(grapheme_width("*\u{fe0f}\u{20e3}"), 2);
+        assert_eq!(grapheme_width("1\u{20e3}"), 1);
+
+        // Variation selectors on other chars do nothing.
+        assert_eq!(grapheme_width("a\u{fe0f}"), 1);
+        assert_eq!(grapheme_width("大\u{fe0e}"), 2);
+
+        let options = WidthOptions::new().emoji_presentation(false);
+        assert_eq!(grapheme_width_with("❤\u{fe0f}", &options), 1);
+        assert_eq!(grapheme_width_with("⌚\u{fe0e}", &options), 2);
+        assert_eq!(grapheme_width_with("1\u{fe0f}\u{20e3}", &options), 1);
+    }
+
+    #[test]
+    fn test_zwj_sequences() {
+        // Emoji joined together are displayed as a single emoji.
+        assert_eq!(grapheme_width("👨‍👩‍👧"), 2);
+        assert_eq!(grapheme_width("👩‍🔬"), 2);
+        assert_eq!(grapheme_width("🏳️‍🌈"), 2);
+        assert_eq!(grapheme_width("🧑🏽‍💻"), 2);
+
+        // Other scripts use joiners for shaping, which doesn't change the width.
+        assert_eq!(grapheme_width("ന\u{d4d}\u{200d}"), 1);
+        assert_eq!(grapheme_width("ه\u{200d}"), 1);
+        assert_eq!(str_width("ന\u{d4d}\u{200d}ന"), 2);
+        assert_eq!(str_width("می\u{200d}خواهم"), 7);
+
+        // A dangling joiner isn't a sequence.
+        assert_eq!(grapheme_width("👩\u{200d}"), 2);
+        assert_eq!(grapheme_width("a\u{200d}"), 1);
+        assert_eq!(grapheme_width("👩\u{200d}🔬\u{200d}"), 2);
+
+        let options = WidthOptions::new().emoji_zwj(false);
+        assert_eq!(grapheme_width_with("👩‍🔬", &options), 4);
+        assert_eq!(grapheme_width_with("ന\u{d4d}\u{200d}", &options), 1);
+    }
+
+    #[test]
+    fn test_tabs() {
+        assert_eq!(str_width("\t"), 8);
+        assert_eq!(str_width("ab\tc"), 9);
+        assert_eq!(str_width("abcdefgh\t"), 16);
+        assert_eq!(str_width("a\t\tb"), 17);
+        assert_eq!(str_width_at("\t", 3), 5);
+        assert_eq!(str_width_at("ab\tc", 3), 6);
+        assert_eq!(str_width_at("ab\tc", 6), 11);
+
+        // Where a single tab starts isn't known.
+        assert_eq!(grapheme_width("\t"), 8);
+        assert_eq!(char_width('\t'), 8);
+
+        let options = WidthOptions::new().tab_width(4);
+        assert_eq!(str_width_with("a\tb", &options), 5);
+        assert_eq!(str_width_at_with("a\tb", 3, &options), 6);
+        assert_eq!(grapheme_width_with("\t", &options), 4);
+
+        let options = WidthOptions::new().tab_width(0);
+        assert_eq!(str_width_with("a\tb", &options), 2);
+        assert_eq!(str_width_at_with("a\tb", 2, &options), 2);
+    }
+
+    #[test]
+    fn test_ambiguous_width() {
+        // cSpell:disable
+        let wide = WidthOptions::new().ambiguous(CellWidth::Wide);
+
+        for ambiguous in ["±", "§", "①", "─"] {
+            assert_eq!(grapheme_width(ambiguous), 1);
+            assert_eq!(grapheme_width_with(ambiguous, &WidthOptions::new()), 1);
+            assert_eq!(grapheme_width_with(ambiguous, &wide), 2);
+        }
+
+        assert_eq!(str_width_with("a±b", &wide), 4);
+        assert_eq!(str_width_with("大§", &wide), 4);
+        assert_eq!(
+            str_width_with("abc大", &wide),
+            str_width("abc大"),
+            "non-ambiguous characters should be unaffected"
+        );
+        // cSpell:enable;
+    }
+
+    #[cfg(feature = "fish")]
+    #[test]
+    fn test_width_policies() {
+        let icons = "\u{e0a0} main";
+        assert_eq!(str_width(icons), 6);
+        assert_eq!(
+            str_width_with(icons, &WidthOptions::new().private_use(CellWidth::Wide)),
+            7
+        );
+
+        let emoji = "⌚💎";
+        assert_eq!(str_width(emoji), 4);
+        assert_eq!(
+            str_width_with(emoji, &WidthOptions::new().widened_in_9(CellWidth::Narrow)),
+            2
+        );
+        assert_eq!(
+            str_width_with("🥰", &WidthOptions::new().widened_in_9(CellWidth::Narrow)),
+            2,
+            "emoji added after Unicode 9 were always wide"
+        );
+    }
+
+    #[cfg(feature = "fish")]
+    #[test]
+    fn test_matches_unicode_width() {
+        // Outside of the classes the widecharwidth tables override, widths come from
+        // `unicode-width`, which has newer data.
+        for c in (0..=u32::from(char::MAX)).filter_map(char::from_u32) {
+            if c == '\t' {
+                continue;
+            }
+
+            if let CharWidthClass::Narrow | CharWidthClass::Wide | CharWidthClass::Unassigned =
+                char_width_class(c)
+            {
+                assert_eq!(
+                    char_width(c),
+                    UnicodeWidthChar::width(c).unwrap_or(0),
+                    "U+{:04X}",
+                    u32::from(c)
+                );
+            }
+        }
+
+        assert_eq!(char_width('\u{3164}'), 0);
+        assert_eq!(char_width('\u{ff9e}'), 0);
+    }
 }
@@ -0,0 +1,279 @@
+//! Wrapping text over multiple lines.
+
+use std::borrow::Cow;
+
+use unicode_segmentation::UnicodeSegmentation;
+
+use crate::{char_width_with, str_width_at_with, take, Ellipsis, WidthOptions};
+
+/// Closing punctuation, which shouldn't start a line.
+const CLOSING: &str = "、。，．・：；！？」』）］｝〕〉》】〗〙〟’”,.:;!?)]}";
+
+/// Opening punctuation, which shouldn't end a line.
+const OPENING: &str = "「『（［｛〔〈《【〖〘〝‘“([{";
+
+/// Wraps `content` into lines at most `width` columns wide, keeping at most `max_lines` lines. If
+/// there is more text than fits, the last line is truncated with a trailing ellipsis character like
+/// [`truncate_str`](crate::truncate_str).
+///
+/// Lines are broken at whitespace, which is dropped from the ends of wrapped lines, and around
+/// wide characters like CJK ideographs, though never before closing punctuation like `。` or after
+/// opening punctuation like `「`. Indentation is also dropped if it leaves no room for the first
+/// word on its line. A word too wide for a line of its own is broken between graphemes instead,
+/// though a single grapheme wider than `width` still gets a line to itself.
+/// Existing line breaks are kept, and every line other than a truncated last line is borrowed from
+/// `content`.
+///
+/// ```
+/// use unicode_ellipsis::wrap_and_truncate;
+///
+/// let description = "Truncates Unicode strings to a certain width";
+///
+/// assert_eq!(
+///     wrap_and_truncate(description, 16, 3),
+///     ["Truncates", "Unicode strings", "to a certain wi…"]
+/// );
+/// assert_eq!(
+///     wrap_and_truncate(description, 16, 2),
+///     ["Truncates", "Unicode strings…"]
+/// );
+/// ```
+#[inline]
+pub fn wrap_and_truncate(content: &str, width: usize, max_lines: usize) -> Vec<Cow<'_, str>> {
+    wrap_and_truncate_with(content, width, max_lines, &WidthOptions::DEFAULT)
+}
+
+/// Wraps `content` into at most `max_lines` lines like [`wrap_and_truncate`], measured with the
+/// given [`WidthOptions`].
+pub fn wrap_and_truncate_with<'a>(
+    content: &'a str,
+    width: usize,
+    max_lines: usize,
+    options: &WidthOptions,
+) -> Vec<Cow<'a, str>> {
+    let mut lines = Vec::new();
+    if width == 0 || max_lines == 0 {
+        return lines;
+    }
+
+    let mut paragraphs = content.lines().peekable();
+    while let Some(paragraph) = paragraphs.next() {
+        let mut rest = paragraph;
+
+        loop {
+            rest = skip_blank_start(rest, width, options);
+            let (line, after) = next_line(rest, width, options);
+
+            if lines.len() + 1 == max_lines && (!after.is_empty() || paragraphs.peek().is_some()) {
+                lines.push(with_ellipsis(rest, width, options).into());
+                return lines;
+            }
+
+            lines.push(line.into());
+            rest = after;
+
+            if rest.is_empty() {
+                break;
+            }
+        }
+    }
+
+    lines
+}
+
+/// Returns `rest` without its leading whitespace if the whitespace would otherwise be all that fits
+/// on its line, so that it doesn't leave a blank line.
+fn skip_blank_start<'a>(rest: &'a str, width: usize, options: &WidthOptions) -> &'a str {
+    let trimmed = rest.trim_start();
+    if trimmed.len() == rest.len() {
+        return rest;
+    }
+
+    let indent = str_width_at_with(&rest[..rest.len() - trimmed.len()], 0, options);
+    let first_word = trimmed.split_word_bounds().next().unwrap_or("");
+
+    if indent + str_width_at_with(first_word, indent, options) > width {
+        trimmed
+    } else {
+        rest
+    }
+}
+
+/// Splits the first line that fits in `width` off of `rest`, returning the line and what comes
+/// after it.
+fn next_line<'a>(rest: &'a str, width: usize, options: &WidthOptions) -> (&'a str, &'a str) {
+    let mut column = 0;
+    let mut has_word = false;
+    let mut breaks_after_previous = false;
+    let mut previous_opens = false;
+    let mut line_break = None;
+
+    for (start, segment) in rest.split_word_bound_indices() {
+        let blank = segment.chars().all(char::is_whitespace);
+        let first = segment.chars().next();
+        let wide = first.is_some_and(|c| char_width_with(c, options) == 2);
+        let closes = first.is_some_and(|c| CLOSING.contains(c));
+
+        if has_word && (blank || wide || breaks_after_previous) && !closes && !previous_opens {
+            line_break = Some(start);
+        }
+
+        column += str_width_at_with(segment, column, options);
+        if column > width {
+            let end = line_break.unwrap_or_else(|| break_graphemes(rest, width, options));
+            return (rest[..end].trim_end(), rest[end..].trim_start());
+        }
+
+        has_word |= !blank;
+        breaks_after_previous = blank || wide;
+        previous_opens = segment
+            .chars()
+            .next_back()
+            .is_some_and(|c| OPENING.contains(c));
+    }
+
+    (rest.trim_end(), "")
+}
+
+/// Returns how many bytes from the start of `rest` fit in `width`, for when there is nowhere better
+/// to break a line. This is always at least one grapheme, even if it is wider than `width`.
+fn break_graphemes(rest: &str, width: usize, options: &WidthOptions) -> usize {
+    match take::<false, _>(rest, width, options).bytes {
+        0 => rest.graphemes(true).next().map_or(0, str::len),
+        bytes => bytes,
+    }
+}
+
+/// Truncates `rest` to fit in `width` with a trailing ellipsis, even if all of it fits.
+fn with_ellipsis(rest: &str, width: usize, options: &WidthOptions) -> String {
+    let ellipsis = Ellipsis::new(Ellipsis::DEFAULT.text, options);
+    let kept = take::<false, _>(rest, width.saturating_sub(ellipsis.width), options);
+
+    let mut line = String::with_capacity(kept.bytes + ellipsis.text.len());
+    line.push_str(&rest[..kept.bytes]);
+    line.push_str(ellipsis.fit(width, options));
+
+    line
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::CellWidth;
+
+    #[test]
+    fn test_wrap() {
+        let content = "the quick brown fox";
+        assert_eq!(
+            wrap_and_truncate(content, 10, 3),
+            ["the quick", "brown fox"]
+        );
+        assert_eq!(wrap_and_truncate(content, 19, 3), [content]);
+        assert_eq!(wrap_and_truncate(content, 9, 3), ["the quick", "brown fox"]);
+        assert_eq!(
+            wrap_and_truncate(content, 5, 5),
+            ["the", "quick", "brown", "fox"]
+        );
+        assert_eq!(wrap_and_truncate("hello, world", 6, 2), ["hello,", "world"]);
+        assert_eq!(
+            wrap_and_truncate("  indented text", 10, 2),
+            ["  indented", "text"]
+        );
+
+        // Indentation that leaves no room for the first word is dropped.
+        assert_eq!(
+            wrap_and_truncate("        hello world", 5, 3),
+            ["hello", "world"]
+        );
+        assert_eq!(wrap_and_truncate("  hello world", 5, 1), ["hell…"]);
+        assert_eq!(wrap_and_truncate("a\n        \nb", 5, 3), ["a", "", "b"]);
+
+        assert!(wrap_and_truncate(content, 10, 0).is_empty());
+        assert!(wrap_and_truncate(content, 0, 3).is_empty());
+        assert!(wrap_and_truncate("", 10, 3).is_empty());
+
+        assert!(wrap_and_truncate(content, 10, 3)
+            .iter()
+            .all(|line| matches!(line, Cow::Borrowed(_))));
+    }
+
+    #[test]
+    fn test_wrap_truncated() {
+        let content = "the quick brown fox";
+        assert_eq!(wrap_and_truncate(content, 10, 1), ["the quick…"]);
+        assert_eq!(wrap_and_truncate(content, 5, 2), ["the", "quic…"]);
+        assert_eq!(wrap_and_truncate(content, 1, 1), ["…"]);
+
+        // The last line fits, but there is more text after it.
+        assert_eq!(wrap_and_truncate("short\nmore", 10, 1), ["short…"]);
+        assert_eq!(wrap_and_truncate("short\nmore", 5, 1), ["shor…"]);
+    }
+
+    #[test]
+    fn test_wrap_long_words() {
+        assert_eq!(
+            wrap_and_truncate("abcdefghij", 4, 5),
+            ["abcd", "efgh", "ij"]
+        );
+        assert_eq!(
+            wrap_and_truncate("see abcdefghij", 4, 5),
+            ["see", "abcd", "efgh", "ij"]
+        );
+        assert_eq!(wrap_and_truncate("施氏", 1, 3), ["施", "氏"]);
+    }
+
+    #[test]
+    fn test_wrap_cjk() {
+        assert_eq!(
+            wrap_and_truncate("施氏食獅史", 4, 3),
+            ["施氏", "食獅", "史"]
+        );
+        assert_eq!(wrap_and_truncate("施氏食獅史", 5, 2), ["施氏", "食獅…"]);
+        assert_eq!(wrap_and_truncate("ab施氏", 5, 2), ["ab施", "氏"]);
+
+        // Closing punctuation stays on the line before, and opening punctuation on the line after.
+        assert_eq!(
+            wrap_and_truncate("施氏。食獅", 4, 3),
+            ["施", "氏。", "食獅"]
+        );
+        assert_eq!(
+            wrap_and_truncate("これは、テストです。", 6, 3),
+            ["これ", "は、", "テス…"]
+        );
+        assert_eq!(
+            wrap_and_truncate("施氏「食獅」", 6, 3),
+            ["施氏", "「食", "獅」"]
+        );
+    }
+
+    #[test]
+    fn test_wrap_with() {
+        let options = WidthOptions::new().ambiguous(CellWidth::Wide);
+
+        // Circled digits are ambiguous, so in a CJK terminal they are wide and lines can break
+        // between them like between ideographs.
+        assert_eq!(wrap_and_truncate("see ①②③", 6, 3), ["see", "①②③"]);
+        assert_eq!(
+            wrap_and_truncate_with("see ①②③", 6, 3, &options),
+            ["see ①", "②③"]
+        );
+
+        // The ellipsis is two columns wide too.
+        assert_eq!(
+            wrap_and_truncate_with("①②③④⑤⑥⑦", 6, 2, &options),
+            ["①②③", "④⑤…"]
+        );
+        assert_eq!(wrap_and_truncate_with("①②", 1, 1, &options), [""]);
+    }
+
+    #[test]
+    fn test_wrap_line_breaks() {
+        assert_eq!(wrap_and_truncate("a\n\nb", 5, 3), ["a", "", "b"]);
+        assert_eq!(wrap_and_truncate("a\r\nb\n", 5, 3), ["a", "b"]);
+        assert_eq!(wrap_and_truncate("a\n\nb", 5, 2), ["a", "…"]);
+        assert_eq!(
+            wrap_and_truncate("first line\nsecond", 6, 4),
+            ["first", "line", "second"]
+        );
+    }
+}